@@ -11,7 +11,10 @@ __version__ = "0.1.0"
     DB_WRITE_ERROR,
     JSON_ERROR,
     ID_ERROR,
-) = range(7)
+    EMPTY_RESULT_ERROR,
+    VALIDATION_ERROR,
+    OVERWRITE_ERROR,
+) = range(10)
 
 ERRORS = {
     DIR_ERROR: "config directory error",
@@ -19,6 +22,9 @@ ERRORS = {
     DB_READ_ERROR: "database read error",
     DB_WRITE_ERROR: "database write error",
     ID_ERROR: "to-do id error",
+    EMPTY_RESULT_ERROR: "extraction matched 0 trips",
+    VALIDATION_ERROR: "feed validation error",
+    OVERWRITE_ERROR: "output files exist and overwriting them wasn't confirmed",
 }
 
 logger = logging.getLogger("GTFS General")
@@ -1,3 +1,5 @@
+from typing import List
+
 from gtfs_general import logger
 
 
@@ -24,3 +26,129 @@ class GtfsFileNotFound(CustomException):
 
     def __str__(self) -> str:
         return f"{self.message}: {self.file_path}"
+
+
+class WorkingDirectoryNotWritable(CustomException):
+    def __init__(self, directory: str) -> None:
+        self.message = f"The working directory isn't writable, check its access rights: {directory}"
+        self.directory = directory
+        logger.error(self.message)
+        super().__init__(self.message)
+
+    def __str__(self) -> str:
+        return self.message
+
+
+class OutputFolderNotWritable(CustomException):
+    def __init__(self, directory: str) -> None:
+        self.message = f"The output folder isn't writable, check its access rights: {directory}"
+        self.directory = directory
+        logger.error(self.message)
+        super().__init__(self.message)
+
+    def __str__(self) -> str:
+        return self.message
+
+
+class OutputRowLimitExceeded(CustomException):
+    def __init__(self, file_name: str, rows: int, max_rows: int) -> None:
+        self.message = f"{file_name} would contain {rows} rows which exceeds the limit of {max_rows} rows"
+        self.file_name = file_name
+        logger.error(self.message)
+        super().__init__(self.message)
+
+    def __str__(self) -> str:
+        return self.message
+
+
+class UnsupportedInputFile(CustomException):
+    def __init__(self, file_path: str) -> None:
+        self.message = f"The input file is neither a .zip nor a .tar.gz archive: {file_path}"
+        self.file_path = file_path
+        logger.error(self.message)
+        super().__init__(self.message)
+
+    def __str__(self) -> str:
+        return self.message
+
+
+class GtfsColumnMissing(CustomException):
+    def __init__(self, file_name: str, column: str) -> None:
+        self.message = f"{file_name} has no values in the column {column}, which this operation needs"
+        self.file_name = file_name
+        self.column = column
+        logger.error(self.message)
+        super().__init__(self.message)
+
+    def __str__(self) -> str:
+        return self.message
+
+
+class WarningAsError(CustomException):
+    def __init__(self, warning: str) -> None:
+        self.message = f"Aborting because warnings are treated as errors: {warning}"
+        self.warning = warning
+        logger.error(self.message)
+        super().__init__(self.message)
+
+    def __str__(self) -> str:
+        return self.message
+
+
+class InvalidPolygon(CustomException):
+    def __init__(self, file_path: str, reason: str) -> None:
+        self.message = f"{file_path} isn't a usable GeoJSON polygon: {reason}"
+        self.file_path = file_path
+        logger.error(self.message)
+        super().__init__(self.message)
+
+    def __str__(self) -> str:
+        return self.message
+
+
+class UnrepresentableCharacter(CustomException):
+    def __init__(self, file_name: str, line: int, character: str, encoding: str) -> None:
+        self.message = f"Line {line} of {file_name} contains {character!r}, which can't be written as {encoding}"
+        self.file_name = file_name
+        self.line = line
+        logger.error(self.message)
+        super().__init__(self.message)
+
+    def __str__(self) -> str:
+        return self.message
+
+
+class DuplicateArchiveMember(CustomException):
+    def __init__(self, file_name: str, members: List[str]) -> None:
+        self.message = f"The archive contains {file_name} more than once, in {', '.join(members)}"
+        self.file_name = file_name
+        self.members = members
+        logger.error(self.message)
+        super().__init__(self.message)
+
+    def __str__(self) -> str:
+        return self.message
+
+
+class DateRangeOutsideFeed(CustomException):
+    def __init__(self, start_date: str, end_date: str, feed_start_date: str, feed_end_date: str) -> None:
+        self.message = (
+            f"The date range {start_date} - {end_date} lies outside of the feed_info.txt window "
+            f"{feed_start_date} - {feed_end_date}, nothing would be extracted"
+        )
+        logger.error(self.message)
+        super().__init__(self.message)
+
+    def __str__(self) -> str:
+        return self.message
+
+
+class UnreadableArchiveMember(CustomException):
+    def __init__(self, file_name: str, reason: str) -> None:
+        self.message = f"The archive entry {file_name} can't be read: {reason}"
+        self.file_name = file_name
+        logger.error(self.message)
+        super().__init__(self.message)
+
+    def __str__(self) -> str:
+        return self.message
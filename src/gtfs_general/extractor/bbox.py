@@ -1,5 +1,7 @@
 from __future__ import annotations
 
+import pandas as pd
+
 
 class Bbox:
     _min_lat: float
@@ -16,3 +18,6 @@ class Bbox:
 
     def contains(self, lat: float, lon: float) -> bool:
         return self._max_lat >= lat >= self._min_lat and self._max_lon >= lon >= self._min_lon
+
+    def contains_points(self, lat: pd.Series, lon: pd.Series) -> pd.Series:
+        return lat.between(self._min_lat, self._max_lat) & lon.between(self._min_lon, self._max_lon)
@@ -2,21 +2,185 @@ from __future__ import annotations
 
 import csv
 import errno
+import functools
 import os
 import shutil
-from datetime import datetime
+import tempfile
+from datetime import date, datetime, timedelta
+from enum import Enum
 from pathlib import Path
-from typing import Dict, List, Set, Tuple, Union
+from typing import Any, Callable, Dict, List, Set, Tuple, Union
 
 import dask.dataframe as ddf
 import pandas as pd
 from tqdm.dask import TqdmCallback
 
 from gtfs_general import logger
-from gtfs_general.exceptions.extractor_exceptions import GtfsFileNotFound
+from gtfs_general.exceptions.extractor_exceptions import (
+    DateRangeOutsideFeed,
+    GtfsColumnMissing,
+    GtfsFileNotFound,
+    OutputFolderNotWritable,
+    OutputRowLimitExceeded,
+    UnrepresentableCharacter,
+)
 from gtfs_general.extractor.bbox import Bbox
-from gtfs_general.extractor.gtfs import GTFS, GtfsDtypes
-from gtfs_general.extractor.utils import parse_date_from_str
+from gtfs_general.extractor.gtfs import GTFS, WEEKDAYS, GtfsDtypes, GtfsFiles
+from gtfs_general.extractor.polygon import Polygon
+from gtfs_general.extractor.utils import (
+    MembershipStrategy,
+    filter_rows_by_ids,
+    parse_date_from_str,
+    seconds_to_time,
+    time_to_seconds,
+)
+
+
+# Tables that a usable feed needs rows in, calendar.txt or calendar_dates.txt alone may be empty
+NON_EMPTY_FILES: Tuple[str, ...] = ("agency.txt", "routes.txt", "stops.txt", "stop_times.txt", "trips.txt")
+
+# The columns of each table that record_id and record_sub_id of translations.txt refer to
+TRANSLATION_RECORD_COLUMNS: Dict[str, List[str]] = {
+    "agency": ["agency_id"],
+    "stops": ["stop_id"],
+    "routes": ["route_id"],
+    "trips": ["trip_id"],
+    "stop_times": ["trip_id", "stop_sequence"],
+    "pathways": ["pathway_id"],
+    "levels": ["level_id"],
+    "feed_info": [],
+    "attributions": ["attribution_id"],
+}
+
+# Columns that identify the rows of each table when appending to an existing feed. feed_info.txt has a single row,
+# rows of the other tables are only dropped if they repeat an existing row completely.
+PRIMARY_KEY_COLUMNS: Dict[str, List[str]] = {
+    "agency.txt": ["agency_id"],
+    "areas.txt": ["area_id"],
+    "booking_rules.txt": ["booking_rule_id"],
+    "calendar.txt": ["service_id"],
+    "calendar_dates.txt": ["service_id", "date"],
+    "fare_attributes.txt": ["fare_id"],
+    "feed_info.txt": [],
+    "frequencies.txt": ["trip_id", "start_time"],
+    "levels.txt": ["level_id"],
+    "pathways.txt": ["pathway_id"],
+    "routes.txt": ["route_id"],
+    "shapes.txt": ["shape_id", "shape_pt_sequence"],
+    "stop_times.txt": ["trip_id", "stop_sequence"],
+    "stops.txt": ["stop_id"],
+    "trips.txt": ["trip_id"],
+}
+
+# Date ranges that are widened to cover the existing and the appended rows of the same key instead of replacing them
+APPEND_DATE_RANGE_COLUMNS: Dict[str, Tuple[str, str]] = {
+    "calendar.txt": ("start_date", "end_date"),
+    "feed_info.txt": ("feed_start_date", "feed_end_date"),
+}
+
+# Rows that belong to a trip or shape as a whole, by the file and column of its id. The appended trips and shapes
+# replace all their existing rows, e.g. stop_times of a changed trip don't stay behind under their old stop_sequence.
+APPEND_REPLACED_GROUPS: Dict[str, Tuple[str, str]] = {
+    "frequencies.txt": ("trips.txt", "trip_id"),
+    "shapes.txt": ("shapes.txt", "shape_id"),
+    "stop_times.txt": ("trips.txt", "trip_id"),
+}
+
+# Columns by which the tables are ordered with sort_output
+SORT_COLUMNS: Dict[str, List[str]] = {
+    "routes.txt": ["route_id"],
+    "trips.txt": ["route_id", "service_id", "trip_id"],
+    "stops.txt": ["stop_id"],
+}
+
+# GTFS tables that aren't filtered yet. Copied unchanged they would refer to removed routes, trips and stops.
+UNFILTERED_GTFS_FILES: Tuple[str, ...] = (
+    "attributions.txt",
+    "fare_leg_join_rules.txt",
+    "fare_leg_rules.txt",
+    "fare_media.txt",
+    "fare_products.txt",
+    "fare_transfer_rules.txt",
+    "location_group_stops.txt",
+    "location_groups.txt",
+    "locations.geojson",
+    "networks.txt",
+    "rider_categories.txt",
+    "route_networks.txt",
+    "timeframes.txt",
+)
+
+
+class QuoteStyle(str, Enum):
+    necessary = "necessary"
+    always = "always"
+    never = "never"
+
+    @property
+    def quoting(self) -> int:
+        if self == QuoteStyle.necessary:
+            return csv.QUOTE_MINIMAL
+        if self == QuoteStyle.never:
+            return csv.QUOTE_NONE
+        return csv.QUOTE_ALL
+
+
+class OutputEncoding(str, Enum):
+    utf8 = "utf8"
+    latin1 = "latin1"
+
+    @property
+    def codec(self) -> str:
+        if self == OutputEncoding.latin1:
+            return "latin-1"
+        return "utf-8"
+
+
+class CalendarForm(str, Enum):
+    dates = "dates"
+    weekly = "weekly"
+
+
+class MissingShapePolicy(str, Enum):
+    keep = "keep"
+    clear = "clear"
+    drop_trip = "drop-trip"
+
+
+class HeadsignStrategy(str, Enum):
+    last_stop = "last-stop"
+    route_name = "route-name"
+
+
+def _write_atomically(extract: Callable[..., List]) -> Callable[..., List]:
+    """
+    Write the files of an extraction to a staging folder inside the output folder and only move them into the
+    output folder once the extraction succeeded. A failed extraction leaves no partial files behind.
+    """
+
+    @functools.wraps(extract)
+    def wrapper(self: Extractor, *args: Any, **kwargs: Any) -> List:
+        output_folder: Path = self._output_folder
+        # Inside the output folder the files are moved on the same file system, which doesn't copy them
+        self._output_folder = Path(tempfile.mkdtemp(prefix=".staging_", dir=output_folder))
+        try:
+            extract(self, *args, **kwargs)
+            if self._append:
+                self._append_to_existing_feed(output_folder)
+            # Checked while the files are staged, so an invalid feed with fail_on_warning doesn't replace the output
+            self._check_output_is_valid()
+            self._project_output()
+            if self._output_encoding != OutputEncoding.utf8:
+                self._transcode_output()
+            file_path: Path
+            for file_path in self._output_folder.iterdir():
+                os.replace(file_path, output_folder.joinpath(file_path.name))
+        finally:
+            shutil.rmtree(self._output_folder, ignore_errors=True)
+            self._output_folder = output_folder
+        return self._get_output_files()
+
+    return wrapper
 
 
 class Extractor(GTFS):
@@ -26,8 +190,118 @@ class Extractor(GTFS):
         output_folder: Path,
         scheduler: str = "multiprocessing",
         cpu_count: int | None = None,
+        quote_style: QuoteStyle = QuoteStyle.always,
+        include_files: List[str] | None = None,
+        line_terminator: str = "\n",
+        membership_strategy: MembershipStrategy = MembershipStrategy.isin,
+        max_output_rows: int | None = None,
+        null_values: List[str] | None = None,
+        preserve_extra_files: bool = False,
+        dedupe_stop_times: bool = False,
+        on_missing_shape: MissingShapePolicy = MissingShapePolicy.keep,
+        max_routes: int | None = None,
+        exclude_route_types: List[int] | None = None,
+        only_route_types: List[int] | None = None,
+        feed_publisher: Tuple[str, str] | None = None,
+        output_prefix: str = "",
+        temp_dir: Path | None = None,
+        sort_output: bool = False,
+        fail_on_warning: bool = False,
+        io_retries: int = 0,
+        projections: Dict[str, List[str]] | None = None,
+        append: bool = False,
+        output_encoding: OutputEncoding = OutputEncoding.utf8,
+        lossy_encoding: bool = False,
     ) -> None:
-        super().__init__(input_object, scheduler=scheduler, cpu_count=cpu_count)
+        if temp_dir is not None and temp_dir.resolve() == output_folder.resolve():
+            # The archive is extracted to its own subfolder of the working directory, so it can't collide with the
+            # written files. The folder has to exist before, though.
+            logger.info("The working directory is the output folder. Extracting the archive to a subfolder of it.")
+            os.makedirs(output_folder, exist_ok=True)
+        super().__init__(
+            input_object,
+            scheduler=scheduler,
+            cpu_count=cpu_count,
+            null_values=null_values,
+            temp_dir=temp_dir,
+            fail_on_warning=fail_on_warning,
+            io_retries=io_retries,
+            require_feed_info=feed_publisher is None,
+        )
+        self._output_folder: Path = self._prepare_output_folder(output_folder)
+        self._quote_style: QuoteStyle = quote_style
+        self._line_terminator: str = line_terminator
+        self._trip_count: int | None = None
+        self._membership_strategy: MembershipStrategy = membership_strategy
+        self._max_output_rows: int | None = max_output_rows
+        self._preserve_extra_files: bool = preserve_extra_files
+        self._dedupe_stop_times: bool = dedupe_stop_times
+        self._on_missing_shape: MissingShapePolicy = on_missing_shape
+        # Only the trips of the first max_routes routes by route_id are kept, e.g. for small demo feeds
+        self._max_routes: int | None = max_routes
+        self._exclude_route_types: Set[int] = set(exclude_route_types or [])
+        self._only_route_types: Set[int] = set(only_route_types or [])
+        # Publisher name and url of the feed_info.txt that is written for feeds without one
+        self._feed_publisher: Tuple[str, str] | None = feed_publisher
+        # Prefixed files don't have the canonical GTFS names, so only use a prefix when asked for one
+        self._output_prefix: str = output_prefix
+        self._sort_output: bool = sort_output
+        self._include_files: Set[str] | None = None
+        # The tables of an in-memory extraction by file name, None while extracting to files
+        self._collected_rows: Dict[str, pd.DataFrame] | None = None
+        self._projections: Dict[str, List[str]] = projections or {}
+        # Merge the extracted rows into the feed that is already in the output folder instead of replacing its files
+        self._append: bool = append
+        self._output_encoding: OutputEncoding = output_encoding
+        # Replace the characters the output encoding can't represent with "?" instead of failing
+        self._lossy_encoding: bool = lossy_encoding
+        self._check_projections()
+        if include_files:
+            self._include_files = set(include_files)
+            for required_file in GtfsFiles.required_files:
+                # A missing feed_info.txt is synthesized even if it isn't included
+                if required_file in self._optional_required_files:
+                    continue
+                if required_file not in self._include_files:
+                    logger.warning(f"{required_file} is required for a valid feed. It will be included anyway.")
+                    self._include_files.add(required_file)
+
+    def _check_projections(self) -> None:
+        file_name: str
+        columns: List[str]
+        for file_name, columns in self._projections.items():
+            # Only GTFS file names are looked up, other names could match any attribute of GtfsFiles
+            if file_name not in GtfsFiles.required_files + GtfsFiles.optional_files:
+                raise GtfsFileNotFound(file_path=file_name)
+            file_path: Path = getattr(self._gtfs_files, Path(file_name).stem)
+            if not file_path.exists():
+                raise GtfsFileNotFound(file_path=file_name)
+            header: pd.Index = pd.read_csv(file_path, nrows=0).columns
+            column: str
+            for column in columns:
+                if column not in header:
+                    raise GtfsColumnMissing(file_name=file_name, column=column)
+            logger.warning(
+                f"{file_name} is only written with the columns {', '.join(columns)}. It may not be a valid GTFS file."
+            )
+
+    def _project_output(self) -> None:
+        """
+        Reduce the finished files to the columns of their projection. The steps of the extraction read the written
+        files back, e.g. to clip the shapes or trim the calendar, so they are only projected at the end.
+        """
+        file_name: str
+        columns: List[str]
+        for file_name, columns in self._projections.items():
+            if self._collected_rows is not None:
+                if file_name in self._collected_rows:
+                    self._collected_rows[file_name] = self._collected_rows[file_name][columns]
+                continue
+            output_path: Path = self._output_path(file_name)
+            if output_path.exists():
+                self._write_csv(pd.read_csv(output_path, dtype=str, keep_default_na=False)[columns], output_path)
+
+    def _prepare_output_folder(self, output_folder: Path) -> Path:
         if not output_folder.exists():
             logger.debug(f"Creating output folder: {output_folder}")
             os.makedirs(output_folder)
@@ -36,19 +310,71 @@ class Extractor(GTFS):
         if not output_folder.exists():
             logger.error(f"Check access rights. Couldn't find and create the output folder {output_folder}")
             raise FileNotFoundError(errno.ENOENT, os.strerror(errno.ENOENT), output_folder)
-        self._output_folder: Path = output_folder
+        if not os.access(output_folder, os.W_OK | os.X_OK):
+            raise OutputFolderNotWritable(directory=output_folder.__str__())
+        return output_folder
+
+    def _output_path(self, file_name: str) -> Path:
+        return self._output_folder.joinpath(f"{self._output_prefix}{file_name}")
+
+    @property
+    def trip_count(self) -> int | None:
+        """
+        Number of trips kept by the last extraction or None if nothing was extracted yet.
+        """
+        return self._trip_count
+
+    def _count_trips(self, trip_ids_to_keep: Set) -> None:
+        self._trip_count = len(trip_ids_to_keep)
+        if self._trip_count == 0:
+            logger.warning("Extraction matched 0 trips. Only the headers will be written, resulting in an empty feed.")
+
+    def _is_included(self, file_path: Path) -> bool:
+        if self._include_files is None or file_path.name in self._include_files:
+            return True
+        logger.info(f"Skip {file_path.name}. It is not in the list of included files.")
+        return False
 
     @staticmethod
-    def __row_filter(rows: pd.DataFrame, ids: Set, columns: List) -> pd.DataFrame:
-        for i in range(len(columns)):
-            column: object = columns[i]
-            if isinstance(column, int):
-                columns[i] = rows.columns[columns]
-        mask = rows[columns].isin(ids)
-        for column in columns:
-            rows[column].where(mask[column], inplace=True)
-            rows = rows[rows[column].notna()]
-        return rows
+    def _sort_key(column: pd.Series) -> pd.Series:
+        # The ids are strings in the schema, but purely numeric ids read more naturally in numeric order
+        if pd.api.types.is_numeric_dtype(column.dtype) or not column.dropna().str.fullmatch(r"\d+").all():
+            return column
+        return pd.to_numeric(column)
+
+    def _sort_rows(self, rows: pd.DataFrame, file_name: str) -> pd.DataFrame:
+        columns: List[str] = [column for column in SORT_COLUMNS.get(file_name, []) if column in rows.columns]
+        if not columns:
+            return rows
+        return rows.sort_values(columns, key=self._sort_key, kind="stable", na_position="last")
+
+    def _write_csv(self, rows: pd.DataFrame, output_path: Path) -> None:
+        file_name: str = output_path.name[len(self._output_prefix) :]
+        if self._sort_output:
+            rows = self._sort_rows(rows, file_name)
+        if self._max_output_rows is not None and len(rows) > self._max_output_rows:
+            raise OutputRowLimitExceeded(file_name=output_path.name, rows=len(rows), max_rows=self._max_output_rows)
+        if self._collected_rows is not None:
+            self._collected_rows[file_name] = rows.reset_index(drop=True)
+            return
+        try:
+            rows.to_csv(
+                output_path,
+                index=False,
+                doublequote=True,
+                quoting=self._quote_style.quoting,
+                lineterminator=self._line_terminator,
+            )
+        except csv.Error:
+            # Fields containing the delimiter, quotes or line breaks would break the file when written unquoted.
+            logger.warning(f"{output_path.name} contains fields that need quoting. Quoting them where necessary.")
+            rows.to_csv(
+                output_path,
+                index=False,
+                doublequote=True,
+                quoting=csv.QUOTE_MINIMAL,
+                lineterminator=self._line_terminator,
+            )
 
     @staticmethod
     def __filter_stops_by_bbox(rows: pd.DataFrame, bbox: Bbox) -> pd.DataFrame:
@@ -57,6 +383,16 @@ class Extractor(GTFS):
         rows.dropna(inplace=True)
         return rows
 
+    @staticmethod
+    def _drop_duplicate_rows(rows: pd.DataFrame, columns: List, file_name: str) -> pd.DataFrame:
+        """
+        Drop the rows that repeat the values of an earlier row in the given key columns.
+        """
+        duplicated: pd.Series = rows.duplicated(subset=columns, keep="first")
+        if duplicated.any():
+            logger.warning(f"Removed {duplicated.sum()} rows of {file_name} with duplicate {', '.join(columns)}")
+        return rows[~duplicated]
+
     def __filter_rows_by_custom_column(
         self,
         file_path: Path | None,
@@ -67,41 +403,42 @@ class Extractor(GTFS):
         return_columns: List = None,
         write_out: bool = False,
         low_memory: bool = False,
+        unique_columns: List | None = None,
+        row_mask: Callable[[pd.DataFrame], pd.Series] | None = None,
     ) -> Tuple:
         if not file_path or not file_path.exists():
             raise GtfsFileNotFound(file_path=file_path.__str__())
-        output_path = self._output_folder.joinpath(file_path.name)
+        output_path = self._output_path(file_path.name)
         csv_chunks: ddf.DataFrame = ddf.read_csv(
             file_path,
             usecols=usecols,
             dtype=dtype,
+            na_values=self._null_values,
             low_memory=low_memory,
             assume_missing=True,
         )
         original_return_columns: List | None = return_columns
         if return_columns:
             return_columns = [column for column in return_columns if column in csv_chunks.columns]
-        ddf_out: ddf.DataFrame = csv_chunks.map_partitions(self.__row_filter, ids=ids, columns=columns)
+        ddf_out: ddf.DataFrame = csv_chunks.map_partitions(
+            filter_rows_by_ids, ids=ids, columns=columns, strategy=self._membership_strategy
+        )
         if write_out:
             with TqdmCallback(desc=f"Filter {file_path.name}/Output to CSV"):
-                ddf_out.compute(scheduler=self._scheduler, num_workers=self._cpu_count).to_csv(
-                    output_path,
-                    index=False,
-                    doublequote=True,
-                    quoting=csv.QUOTE_ALL,
-                )
+                rows: pd.DataFrame = ddf_out.compute(scheduler=self._scheduler, num_workers=self._cpu_count)
+            if row_mask is not None:
+                rows = rows[row_mask(rows)]
+            if unique_columns:
+                rows = self._drop_duplicate_rows(rows, unique_columns, file_path.name)
+            self._write_csv(rows, output_path)
         if isinstance(return_columns, List) and len(return_columns) > 0:
+            results: pd.DataFrame
             if write_out:
-                ddf_out = ddf.read_csv(
-                    output_path,
-                    dtype=dtype,
-                    low_memory=low_memory,
-                    assume_missing=True,
-                )
-            with TqdmCallback(desc="Load results", unit=" chunks"):
-                results: pd.DataFrame = ddf_out[return_columns].compute(
-                    scheduler=self._scheduler, num_workers=self._cpu_count
-                )
+                # The written rows are still in memory, reading the output file again would only repeat the work
+                results = rows[return_columns]
+            else:
+                with TqdmCallback(desc="Load results", unit=" chunks"):
+                    results = ddf_out[return_columns].compute(scheduler=self._scheduler, num_workers=self._cpu_count)
             final_results: Tuple = tuple(set(results[column].dropna().tolist()) for column in results.keys())
             del results
             if isinstance(original_return_columns, List) and len(original_return_columns) > 0:
@@ -117,6 +454,7 @@ class Extractor(GTFS):
             usecols=["stop_id", "stop_lat", "stop_lon"],
             low_memory=False,
             dtype=GtfsDtypes.stops,
+            na_values=self._null_values,
         )
         with TqdmCallback(desc="Filter stops by Bbox", unit=" chunks"):
             ddf_out = csv_chunks.map_partitions(self.__filter_stops_by_bbox, bbox=bbox)
@@ -125,6 +463,10 @@ class Extractor(GTFS):
             )
         return results
 
+    def _get_stops_in_polygon(self, polygon: Polygon) -> Set:
+        stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "stop_lat", "stop_lon"])
+        return set(stops.loc[polygon.contains_points(stops["stop_lat"], stops["stop_lon"]), "stop_id"].dropna())
+
     def _get_trips_of_stop_times(self, stop_ids_to_keep: Set) -> Set:
         return self.__filter_rows_by_custom_column(
             self._gtfs_files.stop_times,
@@ -156,6 +498,78 @@ class Extractor(GTFS):
             write_out=True,
         )
 
+    def _handle_missing_shapes(
+        self, trip_ids: Set, route_ids: Set, service_ids: Set, shape_ids: Set
+    ) -> Tuple[Set, Set, Set, Set]:
+        """
+        Clear the shape_id of the kept trips whose shape is missing from shapes.txt or drop those trips.
+        Returns the trip, route, service and shape ids that are left. Services are only dropped if no kept trip uses
+        them anymore.
+        """
+        if self._on_missing_shape == MissingShapePolicy.keep or not shape_ids:
+            return trip_ids, route_ids, service_ids, shape_ids
+        existing_shape_ids: Set = set()
+        if self._gtfs_files.shapes.exists():
+            existing_shape_ids = set(self.read_table("shapes.txt", usecols=["shape_id"])["shape_id"].dropna())
+        missing_shape_ids: Set = shape_ids - existing_shape_ids
+        if not missing_shape_ids:
+            return trip_ids, route_ids, service_ids, shape_ids
+        trips: pd.DataFrame = self._read_kept_trips(trip_ids)
+        affected: pd.Series = trips["shape_id"].isin(missing_shape_ids)
+        if self._on_missing_shape == MissingShapePolicy.clear:
+            logger.warning(f"Cleared the shape_id of {affected.sum()} trips with shapes missing from shapes.txt")
+            trips.loc[affected, "shape_id"] = None
+        else:
+            logger.warning(f"Dropped {affected.sum()} trips with shapes missing from shapes.txt")
+            unused_service_ids: Set = set(trips.loc[affected, "service_id"].dropna()) - set(
+                trips.loc[~affected, "service_id"].dropna()
+            )
+            service_ids = service_ids - unused_service_ids
+            trips = trips[~affected]
+            self._count_trips(set(trips["trip_id"]))
+        self._write_csv(trips, self._output_path(self._gtfs_files.trips.name))
+        return set(trips["trip_id"]), set(trips["route_id"].dropna()), service_ids, set(trips["shape_id"].dropna())
+
+    def _read_kept_trips(self, trip_ids: Set) -> pd.DataFrame:
+        """
+        Read the rows of the kept trips from the input trips.txt to rewrite the written one.
+        The written trips.txt may only be collected by an in-memory extraction, but _write_csv collects it again.
+        """
+        trips: pd.DataFrame = self.read_table("trips.txt")
+        return trips[trips["trip_id"].isin(trip_ids)]
+
+    def _limit_routes(
+        self, trip_ids: Set, route_ids: Set, service_ids: Set, shape_ids: Set
+    ) -> Tuple[Set, Set, Set, Set]:
+        """
+        Drop the kept trips of routes with an excluded route_type and of routes after the first max_routes routes by
+        route_id. Returns the trip, route, service and shape ids that are left.
+        """
+        kept_route_ids: Set = route_ids
+        if self._exclude_route_types or self._only_route_types:
+            route_types: pd.Series = (
+                self.read_table("routes.txt", usecols=["route_id", "route_type"])
+                .set_index("route_id")["route_type"]
+                .reindex(list(route_ids))
+            )
+            kept: pd.Series = ~route_types.isin(self._exclude_route_types)
+            if self._only_route_types:
+                kept &= route_types.isin(self._only_route_types)
+            kept_route_ids = set(route_types.index[kept])
+        if self._max_routes is not None and len(kept_route_ids) > self._max_routes:
+            kept_route_ids = set(sorted(kept_route_ids)[: self._max_routes])
+        if kept_route_ids == route_ids:
+            return trip_ids, route_ids, service_ids, shape_ids
+        logger.info(f"Keep {len(kept_route_ids)} of {len(route_ids)} routes")
+        route_ids = kept_route_ids
+        trips: pd.DataFrame = self._read_kept_trips(trip_ids)
+        trips = trips[trips["route_id"].isin(route_ids)]
+        self._write_csv(trips, self._output_path(self._gtfs_files.trips.name))
+        self._count_trips(set(trips["trip_id"]))
+        if shape_ids:
+            shape_ids = shape_ids & set(trips["shape_id"].dropna())
+        return set(trips["trip_id"]), route_ids, service_ids & set(trips["service_id"].dropna()), shape_ids
+
     def _filter_routes(self, routes_to_keep: Set) -> Set:
         return self.__filter_rows_by_custom_column(
             self._gtfs_files.routes,
@@ -166,19 +580,40 @@ class Extractor(GTFS):
             dtype=GtfsDtypes.routes,
         )[0]
 
-    def _filter_stop_times_using_trips(self, trip_ids_to_keep: Set) -> Set:
+    def _filter_stop_times_using_trips(
+        self, trip_ids_to_keep: Set, distance_band: Tuple[float, float] | None = None
+    ) -> Tuple[Set, Set]:
+        """
+        Returns the stop ids and the booking rule ids of the kept stop_times.
+        With a distance_band only the stop_times whose shape_dist_traveled lies within it are kept.
+        """
         logger.info("Filter stop_times.txt")
-        return self.__filter_rows_by_custom_column(
+        stop_ids, *booking_rule_ids = self.__filter_rows_by_custom_column(
             self._gtfs_files.stop_times,
             trip_ids_to_keep,
             columns=["trip_id"],
-            return_columns=["stop_id"],
+            return_columns=["stop_id", "pickup_booking_rule_id", "drop_off_booking_rule_id"],
             write_out=True,
             dtype=GtfsDtypes.stop_times,
-        )[0]
+            unique_columns=["trip_id", "stop_sequence"] if self._dedupe_stop_times else None,
+            row_mask=(lambda rows: rows["shape_dist_traveled"].between(*distance_band)) if distance_band else None,
+        )
+        return stop_ids, set().union(*booking_rule_ids)
+
+    def _filter_booking_rules(self, booking_rule_ids_to_keep: Set) -> None:
+        if not self._gtfs_files.booking_rules.exists() or not self._is_included(self._gtfs_files.booking_rules):
+            return
+        logger.info("Filter booking_rules.txt")
+        self.__filter_rows_by_custom_column(
+            self._gtfs_files.booking_rules,
+            booking_rule_ids_to_keep,
+            columns=["booking_rule_id"],
+            write_out=True,
+            dtype=GtfsDtypes.booking_rules,
+        )
 
     def _filter_shapes(self, shape_ids_to_keep: Set) -> None:
-        if not self._gtfs_files.shapes.exists():
+        if not self._gtfs_files.shapes.exists() or not self._is_included(self._gtfs_files.shapes):
             return
         if isinstance(shape_ids_to_keep, Set) and len(shape_ids_to_keep) > 0:
             logger.info("Filter shapes.txt")
@@ -191,8 +626,44 @@ class Extractor(GTFS):
                 low_memory=False,
             )
 
+    def _clip_shapes(self, bbox: Bbox) -> None:
+        """
+        Keep the points of the written shapes that are inside the bbox and one point before and after each of them,
+        so the lines reach the boundary. The shape_pt_sequence of clipped shapes is renumbered from 0.
+        Shapes without a point inside the bbox are kept whole.
+        """
+        output_path: Path = self._output_path(self._gtfs_files.shapes.name)
+        if not output_path.exists():
+            return
+        logger.info("Clip shapes.txt to the bbox")
+        shapes: pd.DataFrame = pd.read_csv(output_path, dtype=GtfsDtypes.shapes).sort_values(
+            ["shape_id", "shape_pt_sequence"]
+        )
+        inside: pd.Series = bbox.contains_points(shapes["shape_pt_lat"], shapes["shape_pt_lon"])
+        by_shape: pd.core.groupby.SeriesGroupBy = inside.groupby(shapes["shape_id"])
+        keep: pd.Series = (
+            inside
+            | by_shape.shift(1, fill_value=False)
+            | by_shape.shift(-1, fill_value=False)
+            | ~by_shape.transform("any")
+        )
+        clipped: pd.Series = (~keep).groupby(shapes["shape_id"]).transform("any")
+        shapes = shapes[keep].copy()
+        clipped = clipped[keep]
+        shapes["shape_pt_sequence"] = shapes["shape_pt_sequence"].where(
+            ~clipped, shapes.groupby("shape_id").cumcount()
+        )
+        logger.info(f"Removed {(~keep).sum()} shape points outside the bbox")
+        self._write_csv(shapes, output_path)
+
     def _filter_agencies(self, agency_ids_to_keep: Set) -> None:
         logger.info("Filter agencies.txt")
+        agencies: pd.DataFrame = self.read_table("agency.txt")
+        if len(agencies) == 1 and not agency_ids_to_keep:
+            # Single agency feeds may leave agency_id blank in routes.txt and agency.txt
+            logger.debug("Keep the single agency of a feed without agency_id references")
+            self._write_csv(agencies, self._output_path(self._gtfs_files.agency.name))
+            return
         self.__filter_rows_by_custom_column(
             self._gtfs_files.agency,
             agency_ids_to_keep,
@@ -222,7 +693,7 @@ class Extractor(GTFS):
         )
 
     def _filter_frequencies_using_trips(self, trip_ids_to_keep: Set) -> None:
-        if self._gtfs_files.frequencies.exists():
+        if self._gtfs_files.frequencies.exists() and self._is_included(self._gtfs_files.frequencies):
             logger.info("Filter frequencies.txt")
             self.__filter_rows_by_custom_column(
                 self._gtfs_files.frequencies,
@@ -232,19 +703,148 @@ class Extractor(GTFS):
                 dtype=GtfsDtypes.frequencies,
             )
 
-    def _filter_stops(self, stop_ids_to_keep: Set) -> None:
+    def _filter_stops(self, stop_ids_to_keep: Set) -> Set:
         logger.info("Filter stops.txt")
-        self.__filter_rows_by_custom_column(
+        return self.__filter_rows_by_custom_column(
             self._gtfs_files.stops,
             stop_ids_to_keep,
             columns=["stop_id"],
+            return_columns=["level_id"],
             write_out=True,
             dtype=GtfsDtypes.stops,
+        )[0]
+
+    def _add_station_nodes(self, stop_ids: Set) -> Set:
+        """
+        Add the parent stations of the stops and all platforms, entrances, generic nodes and boarding areas of these
+        stations. The pathways inside a station connect them, so they and their levels are kept with its stops.
+        """
+        stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "parent_station"])
+        if "parent_station" not in stops.columns:
+            return stop_ids
+        parent_stations: pd.Series = stops.dropna(subset=["parent_station"]).set_index("stop_id")["parent_station"]
+        # Boarding areas belong to a platform, which belongs to the station
+        station_ids: Set = set()
+        parent_ids: Set = set(stop_ids)
+        while parent_ids:
+            parent_ids = set(parent_stations[parent_stations.index.isin(parent_ids)]) - station_ids
+            station_ids |= parent_ids
+        node_ids: Set = set(station_ids)
+        child_ids: Set = set(station_ids)
+        while child_ids:
+            child_ids = set(parent_stations.index[parent_stations.isin(child_ids)]) - node_ids
+            node_ids |= child_ids
+        return stop_ids | node_ids
+
+    def _filter_levels(self, level_ids_to_keep: Set) -> None:
+        if self._gtfs_files.levels.exists() and self._is_included(self._gtfs_files.levels):
+            logger.info("Filter levels.txt")
+            self.__filter_rows_by_custom_column(
+                self._gtfs_files.levels,
+                level_ids_to_keep,
+                columns=["level_id"],
+                write_out=True,
+                dtype=GtfsDtypes.levels,
+            )
+
+    def _filter_pathways_using_stops(self, stop_ids_to_keep: Set) -> None:
+        if self._gtfs_files.pathways.exists() and self._is_included(self._gtfs_files.pathways):
+            logger.info("Filter pathways.txt")
+            self.__filter_rows_by_custom_column(
+                self._gtfs_files.pathways,
+                stop_ids_to_keep,
+                columns=["from_stop_id", "to_stop_id"],
+                write_out=True,
+                dtype=GtfsDtypes.pathways,
+            )
+
+    def _has_output(self, file_name: str) -> bool:
+        if self._collected_rows is not None:
+            return file_name in self._collected_rows
+        return self._output_path(file_name).exists()
+
+    def _output_is_empty(self, file_name: str) -> bool:
+        if self._collected_rows is not None:
+            return self._collected_rows[file_name].empty
+        return pd.read_csv(self._output_path(file_name), nrows=1, dtype=str).empty
+
+    def _check_output_is_valid(self) -> None:
+        """
+        Warn if the output isn't a valid feed, e.g. because a required file is missing or no trips were kept.
+        """
+        missing: List[str] = [file_name for file_name in GtfsFiles.required_files if not self._has_output(file_name)]
+        empty: List[str] = [
+            file_name for file_name in NON_EMPTY_FILES if file_name not in missing and self._output_is_empty(file_name)
+        ]
+        problems: List[str] = []
+        if missing:
+            problems.append(f"{', '.join(missing)} missing")
+        if empty:
+            problems.append(f"{', '.join(empty)} without rows")
+        if problems:
+            self._warn(f"The output isn't a valid feed: {'; '.join(problems)}")
+
+    def _read_output(self, file_name: str, usecols: List[str] | None = None) -> pd.DataFrame:
+        """
+        Read a table that was already written by the running extraction, or collected by an in-memory one.
+        """
+        if self._collected_rows is not None:
+            rows: pd.DataFrame = self._collected_rows[file_name]
+            return rows[[column for column in usecols if column in rows.columns]] if usecols else rows
+        return pd.read_csv(
+            self._output_path(file_name), dtype=str, usecols=(lambda column: column in usecols) if usecols else None
         )
 
+    def _kept_translations(self, table_name: str, translations: pd.DataFrame) -> pd.Series:
+        record_columns: List[str] = TRANSLATION_RECORD_COLUMNS[table_name]
+        file_name: str = f"{table_name}.txt"
+        keep: pd.Series = pd.Series(True, index=translations.index)
+        by_record: pd.Series = translations["record_id"].notna()
+        if by_record.any() and record_columns:
+            records: pd.DataFrame = self._read_output(file_name, usecols=record_columns).dropna().astype(str)
+            kept_records: Set[Tuple] = set(zip(*(records[column] for column in record_columns)))
+            # record_sub_id is only used by tables whose records need two columns, e.g. stop_times
+            record_keys: List[str] = ["record_id", "record_sub_id"][: len(record_columns)]
+            translation_records: pd.Series = pd.Series(
+                list(zip(*(translations[column] for column in record_keys))), index=translations.index
+            )
+            keep[by_record] = translation_records[by_record].map(lambda record: record in kept_records)
+        by_value: pd.Series = ~by_record & translations["field_value"].notna()
+        field_name: str
+        for field_name in translations.loc[by_value, "field_name"].unique():
+            values: pd.DataFrame = self._read_output(file_name, usecols=[field_name])
+            kept_values: Set[str] = set(values[field_name].dropna().astype(str)) if field_name in values else set()
+            matches: pd.Series = by_value & (translations["field_name"] == field_name)
+            keep[matches] = translations.loc[matches, "field_value"].isin(kept_values)
+        return keep
+
+    def _filter_translations(self) -> None:
+        """
+        Keep the translations of the records that are part of the output.
+        Records are matched by record_id and record_sub_id or by field_value. Translations without either refer to
+        the whole table, e.g. feed_info, and are kept if the table is.
+        """
+        if not self._gtfs_files.translations.exists() or not self._is_included(self._gtfs_files.translations):
+            return
+        logger.info("Filter translations.txt")
+        translations: pd.DataFrame = self.read_table("translations.txt")
+        columns: pd.Index = translations.columns
+        column: str
+        for column in ("record_id", "record_sub_id", "field_value"):
+            if column not in translations.columns:
+                translations[column] = pd.Series(pd.NA, index=translations.index, dtype=object)
+        keep: pd.Series = pd.Series(False, index=translations.index)
+        table_name: str
+        table_translations: pd.DataFrame
+        for table_name, table_translations in translations.groupby("table_name"):
+            if table_name not in TRANSLATION_RECORD_COLUMNS or not self._has_output(f"{table_name}.txt"):
+                continue
+            keep[table_translations.index] = self._kept_translations(table_name, table_translations)
+        self._write_csv(translations.loc[keep, columns], self._output_path(self._gtfs_files.translations.name))
+
     def _filter_transfers_using_stops(self, stop_ids_to_keep: Set) -> None:
         # TODO filter_using_custom_column with multiple criterias
-        if self._gtfs_files.transfers.exists():
+        if self._gtfs_files.transfers.exists() and self._is_included(self._gtfs_files.transfers):
             logger.info("Filter transfers.txt")
             self.__filter_rows_by_custom_column(
                 self._gtfs_files.transfers,
@@ -254,24 +854,69 @@ class Extractor(GTFS):
                 dtype=GtfsDtypes.transfers,
             )
 
-    def _filter_calendar_by_dates(self, start_date: datetime, end_date: datetime) -> Set:
+    def _filter_stop_areas_using_stops(self, stop_ids_to_keep: Set) -> Set | None:
+        if not self._gtfs_files.stop_areas.exists():
+            return None
+        logger.info("Filter stop_areas.txt")
+        return self.__filter_rows_by_custom_column(
+            self._gtfs_files.stop_areas,
+            stop_ids_to_keep,
+            columns=["stop_id"],
+            return_columns=["area_id"],
+            write_out=self._is_included(self._gtfs_files.stop_areas),
+            dtype=GtfsDtypes.stop_areas,
+        )[0]
+
+    def _filter_areas(self, area_ids_to_keep: Set | None) -> None:
+        if not self._gtfs_files.areas.exists() or not self._is_included(self._gtfs_files.areas):
+            return
+        if area_ids_to_keep is None:
+            # Without stop_areas.txt there is nothing to filter the areas by
+            logger.info("Write areas.txt to new location")
+            if self._collected_rows is not None:
+                self._collected_rows[self._gtfs_files.areas.name] = self.read_table(self._gtfs_files.areas.name)
+                return
+            shutil.copyfile(self._gtfs_files.areas, self._output_path(self._gtfs_files.areas.name))
+            return
+        logger.info("Filter areas.txt")
+        self.__filter_rows_by_custom_column(
+            self._gtfs_files.areas,
+            area_ids_to_keep,
+            columns=["area_id"],
+            write_out=True,
+            dtype=GtfsDtypes.areas,
+        )
+
+    def _filter_calendar_by_dates(self, start_date: datetime, end_date: datetime, end_exclusive: bool = False) -> Set:
         csv_chunks: ddf.DataFrame = ddf.read_csv(
             self._gtfs_files.calendar,
             dtype=GtfsDtypes.calendar,
             parse_dates=["start_date", "end_date"],
             date_parser=parse_date_from_str,
+            na_values=self._null_values,
             low_memory=False,
         )
         # csv_chunks["start_date"] = ddf.to_datetime(csv_chunks["start_date"].dt.time.astype(str))
+        # A service is valid through its end_date, so an exclusive end boundary must not be reached by it.
+        end_mask: ddf.Series = csv_chunks.end_date < end_date if end_exclusive else csv_chunks.end_date <= end_date
         with TqdmCallback(desc="Filter calendar.txt", unit=" chunks"):
-            results: pd.DataFrame = csv_chunks.loc[
-                (csv_chunks.start_date >= start_date) & (csv_chunks.end_date <= end_date)
-            ].compute(scheduler=self._scheduler, num_workers=self._cpu_count)
-        output_path: Path = self._output_folder.joinpath(self._gtfs_files.calendar.name)
-        results.to_csv(output_path, index=False, doublequote=True, quoting=csv.QUOTE_ALL)
-        return set(results.service_id)
-
-    def _filter_calendar_dates_by_dates(self, start_date: datetime, end_date: datetime) -> Set:
+            results: pd.DataFrame = csv_chunks.loc[(csv_chunks.start_date >= start_date) & end_mask].compute(
+                scheduler=self._scheduler, num_workers=self._cpu_count
+            )
+        output_path: Path = self._output_path(self._gtfs_files.calendar.name)
+        self._write_csv(results, output_path)
+        return self._service_ids(results)
+
+    @staticmethod
+    def _service_ids(rows: pd.DataFrame) -> Set[str]:
+        # Ids from calendar.txt and calendar_dates.txt are merged, so both must be strings even for empty results
+        if rows.empty or "service_id" not in rows.columns:
+            return set()
+        return set(rows["service_id"].dropna().astype(str))
+
+    def _filter_calendar_dates_by_dates(
+        self, start_date: datetime, end_date: datetime, end_exclusive: bool = False
+    ) -> Set:
         if not self._gtfs_files.calendar_dates.exists():
             return set()
         csv_chunks: ddf.DataFrame = ddf.read_csv(
@@ -279,45 +924,550 @@ class Extractor(GTFS):
             dtype=GtfsDtypes.calendar_dates,
             parse_dates=["date"],
             date_parser=parse_date_from_str,
+            na_values=self._null_values,
             low_memory=False,
         )
+        end_mask: ddf.Series = csv_chunks.date < end_date if end_exclusive else csv_chunks.date <= end_date
         with TqdmCallback(desc="Filter calendar_dates.txt", unit=" chunks"):
-            results: pd.DataFrame = csv_chunks.loc[
-                (csv_chunks.date >= start_date) & (csv_chunks.date <= end_date)
-            ].compute(scheduler=self._scheduler, num_workers=self._cpu_count)
-        output_path: Path = self._output_folder.joinpath(self._gtfs_files.calendar.name)
-        results.to_csv(output_path, index=False, doublequote=True, quoting=csv.QUOTE_ALL)
-        return set(results.service_id)
-
-    def _process_common_files(self, service_ids_to_keep: Set, trip_ids_to_keep: Set) -> None:
+            results: pd.DataFrame = csv_chunks.loc[(csv_chunks.date >= start_date) & end_mask].compute(
+                scheduler=self._scheduler, num_workers=self._cpu_count
+            )
+        output_path: Path = self._output_path(self._gtfs_files.calendar_dates.name)
+        self._write_csv(results, output_path)
+        return self._service_ids(results)
+
+    def _process_common_files(
+        self, service_ids_to_keep: Set, trip_ids_to_keep: Set, distance_band: Tuple[float, float] | None = None
+    ) -> None:
         self._filter_calendar_dates_using_services(service_ids_to_keep)
         self._filter_calendar_using_services(service_ids_to_keep)
         self._filter_frequencies_using_trips(trip_ids_to_keep)
 
         # Keep the stop_times used by the trips
-        stop_ids_to_keep: Set = self._filter_stop_times_using_trips(trip_ids_to_keep)
-        self._filter_stops(stop_ids_to_keep)
+        stop_ids_to_keep: Set
+        booking_rule_ids_to_keep: Set
+        stop_ids_to_keep, booking_rule_ids_to_keep = self._filter_stop_times_using_trips(
+            trip_ids_to_keep, distance_band
+        )
+        self._filter_booking_rules(booking_rule_ids_to_keep)
+        station_stop_ids: Set = self._add_station_nodes(stop_ids_to_keep)
+        self._filter_levels(self._filter_stops(station_stop_ids))
         self._filter_transfers_using_stops(stop_ids_to_keep)
+        self._filter_pathways_using_stops(station_stop_ids)
         logger.info(f"{len(stop_ids_to_keep)} stops to keep")
+        self._filter_areas(self._filter_stop_areas_using_stops(stop_ids_to_keep))
+
+        # Rewrite the feed info so it shares the quoting and line endings of the other files
+        if self._gtfs_files.feed_info.exists():
+            logger.info("Write feed_info.txt to new location")
+            self._write_csv(
+                pd.read_csv(self._gtfs_files.feed_info, dtype=GtfsDtypes.feed_info, na_values=self._null_values),
+                self._output_path(self._gtfs_files.feed_info.name),
+            )
 
-        # Copy the feed info
-        logger.info("Copy feed_info.txt to new location")
-        shutil.copyfile(
-            self._gtfs_files.feed_info,
-            self._output_folder.joinpath(self._gtfs_files.feed_info.name),
+        if self._preserve_extra_files:
+            self._copy_extra_files()
+        self._filter_translations()
+
+    def _output_date_range(self) -> Tuple[str | None, str | None]:
+        start_dates: List[str] = []
+        end_dates: List[str] = []
+        file_name: str
+        start_column: str
+        end_column: str
+        for file_name, start_column, end_column in (
+            ("calendar.txt", "start_date", "end_date"),
+            ("calendar_dates.txt", "date", "date"),
+        ):
+            output_path: Path = self._output_path(file_name)
+            if not output_path.exists():
+                continue
+            rows: pd.DataFrame = pd.read_csv(output_path, dtype=str)
+            if start_column in rows.columns and end_column in rows.columns:
+                start_dates.extend(rows[start_column].dropna())
+                end_dates.extend(rows[end_column].dropna())
+        return min(start_dates, default=None), max(end_dates, default=None)
+
+    def _synthesize_feed_info(self, start_date: datetime | None = None, end_date: datetime | None = None) -> None:
+        """
+        Write a minimal feed_info.txt for feeds without one if a publisher is set.
+        Without a date range the dates are taken from the written calendar.txt and calendar_dates.txt.
+        """
+        if self._feed_publisher is None or self._gtfs_files.feed_info.exists():
+            return
+        feed_start_date: str | None
+        feed_end_date: str | None
+        if start_date is not None and end_date is not None:
+            feed_start_date, feed_end_date = start_date.strftime("%Y%m%d"), end_date.strftime("%Y%m%d")
+        else:
+            feed_start_date, feed_end_date = self._output_date_range()
+        agency: pd.DataFrame = self._read_output("agency.txt")
+        languages: List[str] = agency["agency_lang"].dropna().tolist() if "agency_lang" in agency.columns else []
+        logger.info("Write a synthesized feed_info.txt")
+        self._write_csv(
+            pd.DataFrame(
+                [
+                    {
+                        "feed_publisher_name": self._feed_publisher[0],
+                        "feed_publisher_url": self._feed_publisher[1],
+                        # mul marks feeds with multiple or unknown languages
+                        "feed_lang": languages[0] if languages else "mul",
+                        "feed_start_date": feed_start_date,
+                        "feed_end_date": feed_end_date,
+                    }
+                ]
+            ),
+            self._output_path("feed_info.txt"),
         )
 
+    def _copy_extra_files(self) -> None:
+        if self._collected_rows is not None:
+            # Extra files aren't tables, so an in-memory extraction has nothing to return them as
+            return
+        known_files: Set[str] = set(GtfsFiles.required_files + GtfsFiles.optional_files)
+        file_path: Path
+        for file_path in self._feed_folder.iterdir():
+            if not file_path.is_file() or file_path.name in known_files:
+                continue
+            if file_path.name in UNFILTERED_GTFS_FILES:
+                logger.warning(f"Skip {file_path.name}. It is a GTFS table that can't be filtered yet.")
+                continue
+            logger.info(f"Copy extra file {file_path.name} to new location")
+            shutil.copyfile(file_path, self._output_path(file_path.name))
+
+    def _append_to_existing_feed(self, existing_folder: Path) -> None:
+        """
+        Merge each extracted file with the file of the same name in existing_folder. Rows with the primary key of an
+        existing row replace it, so the ids stay unique and rows that were extracted before aren't repeated.
+        The calendar.txt rows of a service and feed_info.txt are widened to cover both date ranges instead, e.g. of
+        two trimmed extractions. Other rows that replace an existing row with other values are warned about.
+        The stop_times.txt and frequencies.txt rows of an appended trip and the points of an appended shape replace
+        all existing rows of it.
+        """
+        # Read before the files are merged, the merged trips.txt would hold the existing trip_ids as well
+        replaced_ids: Dict[str, Set[str]] = {
+            file_name: self._staged_ids(group_file_name, id_column)
+            for file_name, (group_file_name, id_column) in APPEND_REPLACED_GROUPS.items()
+        }
+        file_path: Path
+        for file_path in self._output_folder.glob("*.txt"):
+            existing_path: Path = existing_folder.joinpath(file_path.name)
+            if not existing_path.exists():
+                continue
+            file_name: str = file_path.name[len(self._output_prefix) :]
+            dtype: Dict | str = getattr(GtfsDtypes, Path(file_name).stem, "object")
+            existing_rows: pd.DataFrame = pd.read_csv(existing_path, dtype=dtype, encoding=self._output_encoding.codec)
+            # The staged rows are still UTF-8, the existing files are already in the output encoding
+            new_rows: pd.DataFrame = pd.read_csv(file_path, dtype=dtype, encoding="utf-8")
+            kept_rows: pd.DataFrame = existing_rows
+            if file_name in APPEND_REPLACED_GROUPS:
+                id_column: str = APPEND_REPLACED_GROUPS[file_name][1]
+                if id_column in existing_rows.columns:
+                    kept_rows = existing_rows[~existing_rows[id_column].isin(replaced_ids[file_name])]
+            key_columns: List[str] | None = PRIMARY_KEY_COLUMNS.get(file_name)
+            if key_columns and not set(key_columns) <= set(existing_rows.columns) & set(new_rows.columns):
+                key_columns = None
+            rows: pd.DataFrame
+            start_column: str
+            end_column: str
+            if file_name in APPEND_DATE_RANGE_COLUMNS and key_columns is not None:
+                start_column, end_column = APPEND_DATE_RANGE_COLUMNS[file_name]
+                rows = self._widen_date_ranges(existing_rows, new_rows, key_columns, start_column, end_column)
+            else:
+                if key_columns:
+                    self._warn_about_replaced_rows(file_path.name, kept_rows, new_rows, key_columns)
+                rows = pd.concat([kept_rows, new_rows], ignore_index=True).drop_duplicates(
+                    subset=key_columns, keep="last"
+                )
+            logger.info(f"Append {len(rows) - len(existing_rows)} rows to {file_path.name}")
+            self._write_csv(rows, file_path)
+
+    def _staged_ids(self, file_name: str, id_column: str) -> Set[str]:
+        output_path: Path = self._output_path(file_name)
+        if not output_path.exists():
+            return set()
+        header: pd.Index = pd.read_csv(output_path, nrows=0).columns
+        if id_column not in header:
+            return set()
+        return set(pd.read_csv(output_path, dtype=str, usecols=[id_column], encoding="utf-8")[id_column].dropna())
+
+    @staticmethod
+    def _widen_date_ranges(
+        existing_rows: pd.DataFrame, new_rows: pd.DataFrame, key_columns: List[str], start_column: str, end_column: str
+    ) -> pd.DataFrame:
+        """
+        Keep the new rows, with their date range widened to the earliest start and latest end of the existing rows of
+        the same key. Without key columns the single row of the table is merged.
+        """
+        rows: pd.DataFrame = pd.concat([existing_rows, new_rows], ignore_index=True)
+        if start_column in rows.columns and end_column in rows.columns:
+            # Dates in the YYYYMMDD format compare correctly as strings
+            keys: List[pd.Series] | pd.Series = (
+                [rows[column] for column in key_columns] if key_columns else pd.Series(0, index=rows.index)
+            )
+            rows[start_column] = rows.groupby(keys, dropna=False)[start_column].transform("min")
+            rows[end_column] = rows.groupby(keys, dropna=False)[end_column].transform("max")
+        if not key_columns:
+            return rows.iloc[len(existing_rows) :]
+        return rows.drop_duplicates(subset=key_columns, keep="last")
+
+    def _warn_about_replaced_rows(
+        self, file_name: str, existing_rows: pd.DataFrame, new_rows: pd.DataFrame, key_columns: List[str]
+    ) -> None:
+        # Rows that repeat an existing row completely are no conflict
+        distinct_rows: pd.DataFrame = pd.concat([existing_rows, new_rows], ignore_index=True).drop_duplicates()
+        conflicts: int = distinct_rows.duplicated(subset=key_columns).sum()
+        if conflicts:
+            self._warn(
+                f"{conflicts} appended rows of {file_name} replace existing rows with the same "
+                f"{', '.join(key_columns)} but other values"
+            )
+
+    def _transcode_output(self) -> None:
+        """
+        Rewrite the extracted files, which are written as UTF-8, in the output encoding.
+        Characters the encoding can't represent raise UnrepresentableCharacter, or become "?" with lossy_encoding.
+        """
+        codec: str = self._output_encoding.codec
+        errors: str = "replace" if self._lossy_encoding else "strict"
+        file_path: Path
+        for file_path in self._output_folder.glob("*.txt"):
+            transcoded_path: Path = file_path.with_suffix(".transcoded")
+            with open(file_path, "r", encoding="utf-8", newline="") as source:
+                with open(transcoded_path, "w", encoding=codec, errors=errors, newline="") as target:
+                    line_number: int
+                    line: str
+                    for line_number, line in enumerate(source, start=1):
+                        try:
+                            target.write(line)
+                        except UnicodeEncodeError as error:
+                            raise UnrepresentableCharacter(
+                                file_name=file_path.name,
+                                line=line_number,
+                                character=error.object[error.start : error.end],
+                                encoding=self._output_encoding.value,
+                            )
+            os.replace(transcoded_path, file_path)
+        logger.info(f"Wrote the output as {self._output_encoding.value}")
+
     def _get_output_files(self) -> List:
         files: List = []
         for file in self._output_folder.glob("*.txt"):
-            self._gtfs_files.set_files(file)
+            if not file.name.startswith(self._output_prefix):
+                continue
+            if not self._output_prefix:
+                self._gtfs_files.set_files(file)
             files.append(file)
         return files
 
-    def extract_by_agency(self, agencies: List[str]) -> None:
-        ...
+    def _copy_unchanged_files(self, skip_files: Set[str]) -> None:
+        file_path: Path
+        for file_path in self._feed_folder.glob("*.txt"):
+            if file_path.name not in skip_files:
+                shutil.copyfile(file_path, self._output_path(file_path.name))
+
+    @_write_atomically
+    def convert_calendar(self, to: CalendarForm) -> List:
+        """
+        Convert the services to explicit calendar_dates.txt entries (dates) or to calendar.txt rows (weekly).
+        Services without a regular weekly pattern keep their irregular dates as calendar_dates.txt exceptions.
+        The other files are copied unchanged.
+        """
+        calendar_columns: List[str] = [*WEEKDAYS, "start_date", "end_date", "service_id"]
+        calendar_rows: List[Dict] = []
+        calendar_dates_rows: List[Dict] = []
+        service_id: str
+        dates: Set[date]
+        for service_id, dates in sorted(self._service_dates().items()):
+            if not dates:
+                self._warn(f"Service {service_id} is never active and will be dropped")
+                continue
+            if to == CalendarForm.dates:
+                calendar_dates_rows.extend(
+                    {"service_id": service_id, "date": day.strftime("%Y%m%d"), "exception_type": 1}
+                    for day in sorted(dates)
+                )
+                continue
+            start_day: date = min(dates)
+            end_day: date = max(dates)
+            active_weekdays: Set[int] = {day.weekday() for day in dates}
+            weekly_dates: Set[date] = {
+                start_day + timedelta(days=offset)
+                for offset in range((end_day - start_day).days + 1)
+                if (start_day + timedelta(days=offset)).weekday() in active_weekdays
+            }
+            removed_dates: Set[date] = weekly_dates - dates
+            if len(removed_dates) >= len(dates):
+                # The weekly pattern plus removals would be larger than listing the dates explicitly
+                calendar_dates_rows.extend(
+                    {"service_id": service_id, "date": day.strftime("%Y%m%d"), "exception_type": 1}
+                    for day in sorted(dates)
+                )
+                continue
+            calendar_rows.append(
+                {
+                    **{weekday: int(index in active_weekdays) for index, weekday in enumerate(WEEKDAYS)},
+                    "start_date": start_day.strftime("%Y%m%d"),
+                    "end_date": end_day.strftime("%Y%m%d"),
+                    "service_id": service_id,
+                }
+            )
+            calendar_dates_rows.extend(
+                {"service_id": service_id, "date": day.strftime("%Y%m%d"), "exception_type": 2}
+                for day in sorted(removed_dates)
+            )
+        self._write_csv(
+            pd.DataFrame(calendar_rows, columns=calendar_columns),
+            self._output_path("calendar.txt"),
+        )
+        self._write_csv(
+            pd.DataFrame(calendar_dates_rows, columns=["service_id", "date", "exception_type"]),
+            self._output_path("calendar_dates.txt"),
+        )
+        self._copy_unchanged_files(skip_files={"calendar.txt", "calendar_dates.txt"})
+        return self._get_output_files()
+
+    def _route_names(self) -> pd.Series:
+        routes: pd.DataFrame = self.read_table(
+            "routes.txt", usecols=["route_id", "route_long_name", "route_short_name"]
+        ).set_index("route_id")
+        route_names: pd.Series = pd.Series(pd.NA, index=routes.index, dtype=object)
+        for column in ("route_long_name", "route_short_name"):
+            if column in routes.columns:
+                route_names = route_names.fillna(routes[column])
+        return route_names
+
+    def _last_stop_names(self, trip_ids: Set) -> pd.Series:
+        stop_times: ddf.DataFrame = ddf.read_csv(
+            self._gtfs_files.stop_times,
+            usecols=["trip_id", "stop_id", "stop_sequence"],
+            dtype=GtfsDtypes.stop_times,
+            na_values=self._null_values,
+            low_memory=False,
+        ).map_partitions(filter_rows_by_ids, ids=trip_ids, columns=["trip_id"])
+        last_sequences: ddf.DataFrame = stop_times.groupby("trip_id")["stop_sequence"].max().reset_index()
+        with TqdmCallback(desc="Find last stops", unit=" chunks"):
+            last_stops: pd.DataFrame = (
+                stop_times.merge(last_sequences, on=["trip_id", "stop_sequence"])[["trip_id", "stop_id"]]
+                .compute(scheduler=self._scheduler, num_workers=self._cpu_count)
+                .drop_duplicates("trip_id")
+            )
+        stop_names: pd.Series = self.read_table("stops.txt", usecols=["stop_id", "stop_name"]).set_index("stop_id")[
+            "stop_name"
+        ]
+        return last_stops.set_index("trip_id")["stop_id"].map(stop_names)
+
+    @_write_atomically
+    def backfill_headsigns(self, strategy: HeadsignStrategy = HeadsignStrategy.last_stop) -> List:
+        """
+        Fill blank trip_headsign values with the name of the trip's last stop or with the route name.
+        The last-stop strategy falls back to the route name for trips without a named last stop.
+        The other files are copied unchanged.
+        """
+        trips: pd.DataFrame = self.read_table("trips.txt")
+        if "trip_headsign" not in trips.columns:
+            trips["trip_headsign"] = pd.Series(pd.NA, index=trips.index, dtype=object)
+        blank: pd.Series = trips["trip_headsign"].isna() | (trips["trip_headsign"].str.strip() == "")
+        headsigns: pd.Series = trips.loc[blank, "route_id"].map(self._route_names())
+        if strategy == HeadsignStrategy.last_stop:
+            last_stop_names: pd.Series = trips.loc[blank, "trip_id"].map(
+                self._last_stop_names(set(trips.loc[blank, "trip_id"]))
+            )
+            headsigns = last_stop_names.fillna(headsigns)
+        trips.loc[blank, "trip_headsign"] = headsigns
+        logger.info(f"Filled {headsigns.notna().sum()} of {blank.sum()} blank trip headsigns")
+        self._write_csv(trips, self._output_path(self._gtfs_files.trips.name))
+        self._copy_unchanged_files(skip_files={self._gtfs_files.trips.name})
+        return self._get_output_files()
+
+    @_write_atomically
+    def interpolate_stop_times(self) -> List:
+        """
+        Fill blank arrival_time and departure_time values by interpolating linearly between the surrounding times of
+        the trip. Trips with a complete shape_dist_traveled are interpolated by distance, the others by spacing the
+        stops evenly. A stop with only one of both times gets the other one. Blank times before the first or after the
+        last time of a trip are left blank. The other files are copied unchanged.
+        """
+        stop_times: pd.DataFrame = self.read_table("stop_times.txt")
+        ordered: pd.DataFrame = stop_times.sort_values(["trip_id", "stop_sequence"], kind="stable")
+        trips: pd.Series = ordered["trip_id"]
+        arrivals: pd.Series = time_to_seconds(ordered["arrival_time"])
+        departures: pd.Series = time_to_seconds(ordered["departure_time"])
+        arrivals, departures = arrivals.fillna(departures), departures.fillna(arrivals)
+        known: pd.Series = arrivals.notna()
+
+        positions: pd.Series = ordered.groupby("trip_id").cumcount().astype(float)
+        if "shape_dist_traveled" in ordered.columns:
+            distances: pd.Series = ordered["shape_dist_traveled"]
+            complete: pd.Series = distances.notna().groupby(trips).transform("all")
+            positions = distances.where(complete, positions)
+        previous_positions: pd.Series = positions.where(known).groupby(trips).ffill()
+        previous_times: pd.Series = departures.where(known).groupby(trips).ffill()
+        next_positions: pd.Series = positions.where(known).groupby(trips).bfill()
+        next_times: pd.Series = arrivals.where(known).groupby(trips).bfill()
+        # Stops at the same distance as the previous time get that time
+        fractions: pd.Series = ((positions - previous_positions) / (next_positions - previous_positions)).fillna(0)
+        interpolated: pd.Series = previous_times + fractions * (next_times - previous_times)
+        interpolable: pd.Series = ~known & interpolated.notna()
+        arrivals = arrivals.where(~interpolable, interpolated)
+        departures = departures.where(~interpolable, interpolated)
+
+        unknown_trips: Set = set(trips[~known & ~interpolable])
+        if unknown_trips:
+            self._warn(
+                f"{len(unknown_trips)} trips have blank times before their first or after their last time, "
+                "they are left blank"
+            )
+        column: str
+        times: pd.Series
+        for column, times in (("arrival_time", arrivals), ("departure_time", departures)):
+            blank: pd.Series = ordered[column].isna() & times.notna()
+            stop_times.loc[blank[blank].index, column] = seconds_to_time(times[blank])
+        logger.info(f"Interpolated the times of {interpolable.sum()} stop_times")
+        self._write_csv(stop_times, self._output_path(self._gtfs_files.stop_times.name))
+        self._copy_unchanged_files(skip_files={self._gtfs_files.stop_times.name})
+        return self._get_output_files()
+
+    def _filter_routes_by_agency_ids(self, agency_ids: Set) -> Tuple:
+        agency: pd.DataFrame = self.read_table("agency.txt")
+        if len(agency) == 1:
+            sole_agency_id: str | None = agency["agency_id"].iloc[0] if "agency_id" in agency.columns else None
+            routes: pd.DataFrame = self.read_table("routes.txt", usecols=["route_id", "agency_id"])
+            if "agency_id" not in routes.columns:
+                routes["agency_id"] = pd.Series(pd.NA, index=routes.index, dtype="string")
+            # Single agency feeds may leave agency_id blank, so the routes without one belong to the sole agency
+            kept: pd.Series | None = None
+            if pd.isna(sole_agency_id):
+                kept = pd.Series(True, index=routes.index)
+            elif sole_agency_id in agency_ids:
+                kept = routes["agency_id"].isna() | routes["agency_id"].isin(agency_ids)
+            if kept is not None:
+                route_ids: Set = set(routes.loc[kept, "route_id"].dropna())
+                return route_ids, self._filter_routes(route_ids)
+        return self.__filter_rows_by_custom_column(
+            self._gtfs_files.routes,
+            agency_ids,
+            columns=["agency_id"],
+            return_columns=["route_id", "agency_id"],
+            write_out=True,
+            dtype=GtfsDtypes.routes,
+        )
+
+    def _filter_trips_by_route_ids(self, route_ids_to_keep: Set) -> Tuple:
+        return self.__filter_rows_by_custom_column(
+            self._gtfs_files.trips,
+            route_ids_to_keep,
+            columns=["route_id"],
+            return_columns=["service_id", "trip_id", "shape_id"],
+            write_out=True,
+            dtype=GtfsDtypes.trips,
+        )
+
+    @_write_atomically
+    def extract_by_agency(self, agencies: List[str]) -> List:
+        """
+        Extract the routes of the given agency_ids and everything they use.
+        """
+        agency_ids_to_keep: Set = set(agencies)
+        logger.info(f"Filter routes of the agencies {', '.join(sorted(agency_ids_to_keep))}")
+        route_ids_to_keep: Set
+        route_ids_to_keep, agency_ids_to_keep = self._filter_routes_by_agency_ids(agency_ids_to_keep)
+        logger.info(f"Found {len(route_ids_to_keep)} routes of the agencies")
+
+        logger.info("Filter trips from selected routes")
+        service_ids_to_keep: Set
+        trip_ids_to_keep: Set
+        shape_ids_to_keep: Set
+        (
+            service_ids_to_keep,
+            trip_ids_to_keep,
+            shape_ids_to_keep,
+        ) = self._filter_trips_by_route_ids(route_ids_to_keep)
+        limited_route_ids: Set
+        trip_ids_to_keep, limited_route_ids, service_ids_to_keep, shape_ids_to_keep = self._limit_routes(
+            trip_ids_to_keep, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep
+        )
+        if limited_route_ids != route_ids_to_keep:
+            # routes.txt was written before the trips were known
+            route_ids_to_keep = limited_route_ids
+            agency_ids_to_keep = self._filter_routes(route_ids_to_keep)
+        trip_ids_to_keep, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep = self._handle_missing_shapes(
+            trip_ids_to_keep, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep
+        )
+        logger.info(f"Found {len(trip_ids_to_keep)} trips of the agencies")
+        self._count_trips(trip_ids_to_keep)
+
+        self._filter_agencies(agency_ids_to_keep)
+        self._filter_shapes(shape_ids_to_keep)
+        self._process_common_files(service_ids_to_keep=service_ids_to_keep, trip_ids_to_keep=trip_ids_to_keep)
+        self._synthesize_feed_info()
+
+        return self._get_output_files()
+
+    def _get_trips_in_distance_band(self, min_distance: float, max_distance: float) -> Set:
+        column: str = "shape_dist_traveled"
+        if column not in pd.read_csv(self._gtfs_files.stop_times, nrows=0).columns:
+            raise GtfsColumnMissing(file_name=self._gtfs_files.stop_times.name, column=column)
+        csv_chunks: ddf.DataFrame = ddf.read_csv(
+            self._gtfs_files.stop_times,
+            usecols=["trip_id", column],
+            dtype=GtfsDtypes.stop_times,
+            na_values=self._null_values,
+            low_memory=False,
+        )
+        with TqdmCallback(desc="Filter stop_times.txt by distance", unit=" chunks"):
+            distances: pd.DataFrame = csv_chunks.compute(scheduler=self._scheduler, num_workers=self._cpu_count)
+        missing: pd.Series = distances[column].isna()
+        if missing.all():
+            raise GtfsColumnMissing(file_name=self._gtfs_files.stop_times.name, column=column)
+        if missing.any():
+            self._warn(f"{missing.sum()} stop_times without {column} are dropped")
+        return set(distances.loc[distances[column].between(min_distance, max_distance), "trip_id"].dropna())
+
+    @_write_atomically
+    def extract_by_distance(self, min_distance: float, max_distance: float) -> List:
+        """
+        Extract the stop_times whose shape_dist_traveled lies between min_distance and max_distance, both inclusive.
+        Trips without a stop in that band are dropped.
+        """
+        logger.info(f"Filter stop_times.txt between {min_distance} and {max_distance} along the shapes")
+        trip_ids_to_keep: Set = self._get_trips_in_distance_band(min_distance, max_distance)
+        logger.info(f"Found {len(trip_ids_to_keep)} trips with stops in the distance band")
 
-    def extract_by_bbox(self, bbox: Bbox) -> List:
+        logger.info("Filter routes from selected trips")
+        route_ids_to_keep: Set
+        service_ids_to_keep: Set
+        shape_ids_to_keep: Set
+        (
+            route_ids_to_keep,
+            service_ids_to_keep,
+            shape_ids_to_keep,
+        ) = self._filter_trips(trip_ids_to_keep)
+        trip_ids_to_keep, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep = self._limit_routes(
+            trip_ids_to_keep, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep
+        )
+        trip_ids_to_keep, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep = self._handle_missing_shapes(
+            trip_ids_to_keep, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep
+        )
+        self._count_trips(trip_ids_to_keep)
+
+        self._filter_agencies(self._filter_routes(route_ids_to_keep))
+        self._filter_shapes(shape_ids_to_keep)
+        self._process_common_files(
+            service_ids_to_keep=service_ids_to_keep,
+            trip_ids_to_keep=trip_ids_to_keep,
+            distance_band=(min_distance, max_distance),
+        )
+        self._synthesize_feed_info()
+
+        return self._get_output_files()
+
+    @_write_atomically
+    def extract_by_bbox(self, bbox: Bbox, clip_shapes: bool = False) -> List:
+        """
+        Extract the trips that stop inside the bbox.
+        With clip_shapes the kept shapes are trimmed to the bbox.
+        """
         logger.info("Filter stops within bbox")
         stop_ids_in_bbox = self._get_stops_in_bbox(bbox)
         logger.info("Found {} stops in bbox".format(len(stop_ids_in_bbox)))
@@ -326,6 +1476,7 @@ class Extractor(GTFS):
         trip_ids: Set
         trip_ids = self._get_trips_of_stop_times(stop_ids_in_bbox)
         logger.info("Found {} trips in bbox".format(len(trip_ids)))
+        self._count_trips(trip_ids)
 
         logger.info("Filter routes from selected trips")
         route_ids_to_keep: Set
@@ -336,6 +1487,12 @@ class Extractor(GTFS):
             service_ids_to_keep,
             shape_ids_to_keep,
         ) = self._filter_trips(trip_ids)
+        trip_ids, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep = self._limit_routes(
+            trip_ids, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep
+        )
+        trip_ids, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep = self._handle_missing_shapes(
+            trip_ids, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep
+        )
         logger.info("Found {} routes in bbox".format(len(route_ids_to_keep)))
 
         logger.info("Filter agencies")
@@ -345,15 +1502,179 @@ class Extractor(GTFS):
         logger.info("Found {} agencies in bbox".format(len(agency_ids_to_keep)))
 
         self._filter_shapes(shape_ids_to_keep)
+        if clip_shapes:
+            self._clip_shapes(bbox)
 
         self._process_common_files(service_ids_to_keep=service_ids_to_keep, trip_ids_to_keep=trip_ids)
+        self._synthesize_feed_info()
 
         return self._get_output_files()
 
-    def extract_by_date(self, start_date: datetime, end_date: datetime) -> List:
+    @_write_atomically
+    def extract_by_polygon(self, polygon: Polygon) -> List:
+        """
+        Extract the trips that stop inside the polygon.
+        """
+        logger.info("Filter stops within polygon")
+        stop_ids_in_polygon: Set = self._get_stops_in_polygon(polygon)
+        logger.info(f"Found {len(stop_ids_in_polygon)} stops in polygon")
+
+        logger.info("Filter trips from selected stops")
+        trip_ids: Set = self._get_trips_of_stop_times(stop_ids_in_polygon)
+        logger.info(f"Found {len(trip_ids)} trips in polygon")
+        self._extract_trips(trip_ids)
+        return self._get_output_files()
+
+    def _extract_trips(self, trip_ids: Set) -> None:
+        self._count_trips(trip_ids)
+        route_ids_to_keep: Set
+        service_ids_to_keep: Set
+        shape_ids_to_keep: Set
+        route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep = self._filter_trips(trip_ids)
+        trip_ids, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep = self._limit_routes(
+            trip_ids, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep
+        )
+        trip_ids, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep = self._handle_missing_shapes(
+            trip_ids, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep
+        )
+
+        logger.info("Filter agencies")
+        agency_ids_to_keep: Set = self._filter_routes(route_ids_to_keep)
+        self._filter_agencies(agency_ids_to_keep)
+        self._filter_shapes(shape_ids_to_keep)
+
+        self._process_common_files(service_ids_to_keep=service_ids_to_keep, trip_ids_to_keep=trip_ids)
+        self._synthesize_feed_info()
+
+    @_write_atomically
+    def extract_sample(self, trips_per_route: int, seed: int = 0) -> List:
+        """
+        Extract a random sample of at most trips_per_route trips of each route and everything they use.
+        The same seed always draws the same trips, the fixed default keeps runs reproducible.
+        """
+        logger.info(f"Sample {trips_per_route} trips per route with seed {seed}")
+        trips: pd.DataFrame = self.read_table("trips.txt", usecols=["route_id", "trip_id"])
+        trip_ids: Set = set(
+            trips.sample(frac=1, random_state=seed).groupby("route_id", dropna=False).head(trips_per_route)["trip_id"]
+        )
+        logger.info(f"Sampled {len(trip_ids)} of {len(trips)} trips")
+        self._extract_trips(trip_ids)
+        return self._get_output_files()
+
+    @_write_atomically
+    def extract_by_trip_ids(self, trip_ids: List[str]) -> List:
+        """
+        Extract the given trips and everything they use. trip_ids missing from trips.txt are reported and skipped.
+        """
+        known_trip_ids: Set = set(self.read_table("trips.txt", usecols=["trip_id"])["trip_id"])
+        trip_ids_to_keep: Set = set(trip_ids) & known_trip_ids
+        missing_trip_ids: Set = set(trip_ids) - known_trip_ids
+        if missing_trip_ids:
+            self._warn(f"Trips {', '.join(sorted(missing_trip_ids))} are missing from trips.txt")
+        logger.info(f"Extract {len(trip_ids_to_keep)} trips")
+        self._extract_trips(trip_ids_to_keep)
+        return self._get_output_files()
+
+    def extract_by_date_rows(self, start_date: datetime, end_date: datetime) -> Dict[str, pd.DataFrame]:
+        """
+        Extract the services running between start_date and end_date, both inclusive, like extract_by_date, but
+        return the tables as DataFrames by file name instead of writing them. Extra files aren't returned.
+        All kept tables are held in memory until the extraction ends, while extract_by_date writes each table as soon
+        as it is filtered. Prefer the files for large feeds.
+        """
+        collected_rows: Dict[str, pd.DataFrame] = {}
+        self._collected_rows = collected_rows
+        try:
+            self.extract_by_date(start_date, end_date)
+        finally:
+            self._collected_rows = None
+        return collected_rows
+
+    def extract_multiple_ranges(
+        self, ranges: List[Tuple[datetime, datetime, Path]], end_exclusive: bool = False
+    ) -> List[List]:
+        """
+        Extract several date ranges, each into its own output folder.
+        The input is only extracted once. The options of the Extractor apply to every range.
+        """
+        results: List[List] = []
+        start_date: datetime
+        end_date: datetime
+        output_folder: Path
+        for start_date, end_date, output_folder in ranges:
+            # The previous range pointed the files to its output, so read from the feed again
+            self._gtfs_files = GtfsFiles()
+            file_path: Path
+            for file_path in self._feed_folder.glob("*.txt"):
+                self._gtfs_files.set_files(file_path)
+            self._output_folder = self._prepare_output_folder(output_folder)
+            logger.info(f"Extract the range from {start_date} to {end_date} to {output_folder}")
+            results.append(self.extract_by_date(start_date, end_date, end_exclusive=end_exclusive))
+        return results
+
+    def _clamp_to_feed_info(self, start_date: datetime, end_date: datetime) -> Tuple[datetime, datetime]:
+        feed_start_date, feed_end_date = self.feed_info_date_range()
+        if (feed_start_date and end_date < feed_start_date) or (feed_end_date and start_date > feed_end_date):
+            raise DateRangeOutsideFeed(
+                start_date=start_date.strftime("%Y%m%d"),
+                end_date=end_date.strftime("%Y%m%d"),
+                feed_start_date=feed_start_date.strftime("%Y%m%d") if feed_start_date else "",
+                feed_end_date=feed_end_date.strftime("%Y%m%d") if feed_end_date else "",
+            )
+        if feed_start_date and start_date < feed_start_date:
+            start_date = feed_start_date
+        if feed_end_date and end_date > feed_end_date:
+            end_date = feed_end_date
+        logger.info(f"Clamped the date range to {start_date} - {end_date} using feed_info.txt")
+        return start_date, end_date
+
+    def _trim_calendar(self, start_date: datetime, end_date: datetime) -> None:
+        output_path: Path = self._output_path(self._gtfs_files.calendar.name)
+        calendar: pd.DataFrame = pd.read_csv(output_path, dtype=GtfsDtypes.calendar)
+        # Dates in the YYYYMMDD format compare correctly as strings
+        first_day: str = start_date.strftime("%Y%m%d")
+        last_day: str = end_date.strftime("%Y%m%d")
+        calendar["start_date"] = calendar["start_date"].where(calendar["start_date"] >= first_day, first_day)
+        calendar["end_date"] = calendar["end_date"].where(calendar["end_date"] <= last_day, last_day)
+        outside: pd.Series = calendar["start_date"] > calendar["end_date"]
+        if outside.any():
+            logger.info(f"Drop {outside.sum()} calendar.txt rows outside of the date range.")
+            added_service_ids: Set[str] = set()
+            if self._has_output("calendar_dates.txt"):
+                calendar_dates: pd.DataFrame = self._read_output(
+                    "calendar_dates.txt", usecols=["service_id", "exception_type"]
+                )
+                additions: pd.Series = calendar_dates["exception_type"].astype(str) == "1"
+                added_service_ids = set(calendar_dates.loc[additions, "service_id"])
+            without_dates: List[str] = sorted(set(calendar.loc[outside, "service_id"]) - added_service_ids)
+            if without_dates:
+                logger.warning(
+                    f"The services {', '.join(without_dates)} have no calendar_dates.txt additions in the date range "
+                    "either, so they don't run on any day of the output."
+                )
+        self._write_csv(calendar[~outside], output_path)
+
+    @_write_atomically
+    def extract_by_date(
+        self,
+        start_date: datetime,
+        end_date: datetime,
+        end_exclusive: bool = False,
+        clamp_to_feed_info: bool = False,
+        trim_calendar: bool = False,
+    ) -> List:
+        """
+        Extract the services running between start_date and end_date.
+        The end_date is inclusive by default. Set end_exclusive to stop before it.
+        With clamp_to_feed_info the range is intersected with the feed_start_date/feed_end_date of feed_info.txt.
+        A range entirely outside of that window raises DateRangeOutsideFeed instead of extracting an empty feed.
+        With trim_calendar the start_date/end_date of the kept calendar.txt rows are clamped to the range.
+        """
+        if clamp_to_feed_info:
+            start_date, end_date = self._clamp_to_feed_info(start_date, end_date)
         logger.info(f"Filter calendar.txt between {start_date} and {end_date}")
-        service_ids_to_keep: Set = self._filter_calendar_by_dates(start_date, end_date)
-        service_ids_to_keep_addition: Set = self._filter_calendar_dates_by_dates(start_date, end_date)
+        service_ids_to_keep: Set = self._filter_calendar_by_dates(start_date, end_date, end_exclusive)
+        service_ids_to_keep_addition: Set = self._filter_calendar_dates_by_dates(start_date, end_date, end_exclusive)
         service_ids_to_keep.update(service_ids_to_keep_addition)
         logger.info(f"Found {len(service_ids_to_keep)} calendar entries")
 
@@ -364,7 +1685,14 @@ class Extractor(GTFS):
             trip_ids_to_keep,
             shape_ids_to_keep,
         ) = self._filter_trips_by_service_ids(service_ids_to_keep)
+        trip_ids_to_keep, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep = self._limit_routes(
+            trip_ids_to_keep, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep
+        )
+        trip_ids_to_keep, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep = self._handle_missing_shapes(
+            trip_ids_to_keep, route_ids_to_keep, service_ids_to_keep, shape_ids_to_keep
+        )
         logger.info(f"Found {len(trip_ids_to_keep)} trips between dates")
+        self._count_trips(trip_ids_to_keep)
 
         logger.info("Filter agencies")
         agency_ids_to_keep: Set
@@ -379,4 +1707,10 @@ class Extractor(GTFS):
             trip_ids_to_keep=trip_ids_to_keep,
         )
 
+        last_date: datetime = end_date - timedelta(days=1) if end_exclusive else end_date
+        if trim_calendar:
+            logger.info("Trim calendar.txt to the date range")
+            self._trim_calendar(start_date, last_date)
+        self._synthesize_feed_info(start_date, last_date)
+
         return self._get_output_files()
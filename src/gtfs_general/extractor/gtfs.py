@@ -1,18 +1,78 @@
 from __future__ import annotations
 
+import codecs
+import csv
 import errno
+import hashlib
+import logging
 import os
+import shutil
+import tarfile
 import tempfile
+import time
 import zipfile
+from datetime import date, datetime, timedelta
+from enum import Enum
 from pathlib import Path
-from typing import Any, Dict, Tuple
+from typing import IO, Any, Callable, Dict, Iterator, List, Set, Tuple, Type, TypeVar
 
 import numpy as np
+import pandas as pd
 from dask import dataframe as ddf
 
 from gtfs_general import logger
-from gtfs_general.exceptions.extractor_exceptions import GtfsIncompleteException
-from gtfs_general.extractor.utils import parse_date_from_str
+from gtfs_general.exceptions.extractor_exceptions import (
+    DuplicateArchiveMember,
+    GtfsFileNotFound,
+    GtfsIncompleteException,
+    UnreadableArchiveMember,
+    UnsupportedInputFile,
+    WarningAsError,
+    WorkingDirectoryNotWritable,
+)
+from gtfs_general.extractor.bbox import Bbox
+from gtfs_general.extractor.utils import EARTH_RADIUS_METERS, haversine_meters, parse_date_from_str, time_to_seconds
+
+T = TypeVar("T")
+
+WEEKDAYS: Tuple[str, ...] = ("monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday")
+# Delay before the first retry of a failed archive read, it doubles with every further retry
+IO_RETRY_DELAY_SECONDS: float = 0.1
+# Rows per file whose average size is used to estimate the size of an extraction
+ESTIMATE_SAMPLE_ROWS: int = 1000
+# Longest calendar.txt range that is expanded to single dates, longer ranges are usually open-ended services
+MAX_SERVICE_DAYS: int = 3660
+# Optional files that are needed once the other file refers to them. Their absence is a warning instead of info
+CONDITIONALLY_REQUIRED_FILES: Dict[str, str] = {
+    "areas.txt": "stop_areas.txt",
+    "fare_attributes.txt": "fare_rules.txt",
+}
+
+NDJSON_BATCH_ROWS: int = 100_000
+# Errors of zip entries with a corrupt header or data, an unsupported compression or encryption
+UNREADABLE_ZIP_ENTRY_ERRORS: Tuple[Type[Exception], ...] = (zipfile.BadZipFile, NotImplementedError, RuntimeError)
+
+
+class ArchiveType(str, Enum):
+    zip = "zip"
+    tar_gz = "tar.gz"
+
+    @staticmethod
+    def detect(input_file: Path) -> ArchiveType:
+        with open(input_file, "rb") as fp:
+            magic_bytes: bytes = fp.read(4)
+        archive_type: ArchiveType
+        if magic_bytes in (b"PK\x03\x04", b"PK\x05\x06"):
+            archive_type = ArchiveType.zip
+        elif magic_bytes.startswith(b"\x1f\x8b"):
+            archive_type = ArchiveType.tar_gz
+        else:
+            raise UnsupportedInputFile(file_path=input_file.__str__())
+        if not input_file.name.endswith((".zip",) if archive_type == ArchiveType.zip else (".tar.gz", ".tgz")):
+            logger.warning(
+                f"The extension of {input_file.name} doesn't match its content. Reading it as .{archive_type.value}."
+            )
+        return archive_type
 
 
 class GtfsDtypes:
@@ -73,6 +133,7 @@ class GtfsDtypes:
         "stop_id": np.str_,
         "stop_code": np.str_,
         "stop_name": np.str_,
+        "tts_stop_name": np.str_,
         "stop_desc": np.str_,
         "stop_lat": np.float_,
         "stop_lon": np.float_,
@@ -84,6 +145,7 @@ class GtfsDtypes:
         "wheelchair_boarding": "Int64",
         "level_id": np.str_,
         "platform_code": np.str_,
+        "stop_access": "Int64",
     }
     trips: Dict = {
         "route_id": np.str_,
@@ -110,6 +172,30 @@ class GtfsDtypes:
         "continuous_drop_off": "Int64",
         "shape_dist_traveled": np.float_,
         "timepoint": "Int64",
+        # GTFS-Flex
+        "location_group_id": np.str_,
+        "location_id": np.str_,
+        "start_pickup_drop_off_window": np.str_,
+        "end_pickup_drop_off_window": np.str_,
+        "pickup_booking_rule_id": np.str_,
+        "drop_off_booking_rule_id": np.str_,
+    }
+    booking_rules: Dict = {
+        "booking_rule_id": np.str_,
+        "booking_type": "Int64",
+        "prior_notice_duration_min": "Int64",
+        "prior_notice_duration_max": "Int64",
+        "prior_notice_last_day": "Int64",
+        "prior_notice_last_time": np.str_,
+        "prior_notice_start_day": "Int64",
+        "prior_notice_start_time": np.str_,
+        "prior_notice_service_id": np.str_,
+        "message": np.str_,
+        "pickup_message": np.str_,
+        "drop_off_message": np.str_,
+        "phone_number": np.str_,
+        "info_url": np.str_,
+        "booking_url": np.str_,
     }
 
     # Optional
@@ -127,29 +213,163 @@ class GtfsDtypes:
         "headway_secs": "Int64",
         "exact_times": "Int64",
     }
+    fare_attributes: Dict = {
+        "fare_id": np.str_,
+        "price": np.float_,
+        "currency_type": np.str_,
+        "payment_method": "Int64",
+        "transfers": "Int64",
+        "agency_id": np.str_,
+        "transfer_duration": "Int64",
+    }
+    fare_rules: Dict = {
+        "fare_id": np.str_,
+        "route_id": np.str_,
+        "origin_id": np.str_,
+        "destination_id": np.str_,
+        "contains_id": np.str_,
+    }
+    areas: Dict = {
+        "area_id": np.str_,
+        "area_name": np.str_,
+    }
+    stop_areas: Dict = {
+        "area_id": np.str_,
+        "stop_id": np.str_,
+    }
     transfers: Dict = {
         "from_stop_id": np.str_,
         "to_stop_id": np.str_,
+        "from_route_id": np.str_,
+        "to_route_id": np.str_,
+        "from_trip_id": np.str_,
+        "to_trip_id": np.str_,
         "transfer_type": "Int64",
         "min_transfer_time": "Int64",
     }
+    levels: Dict = {
+        "level_id": np.str_,
+        "level_index": np.float_,
+        "level_name": np.str_,
+    }
+    pathways: Dict = {
+        "pathway_id": np.str_,
+        "from_stop_id": np.str_,
+        "to_stop_id": np.str_,
+        "pathway_mode": "Int64",
+        "is_bidirectional": "Int64",
+        "length": np.float_,
+        "traversal_time": "Int64",
+        "stair_count": "Int64",
+        "max_slope": np.float_,
+        "min_width": np.float_,
+        "signposted_as": np.str_,
+        "reversed_signposted_as": np.str_,
+    }
+    translations: Dict = {
+        "table_name": np.str_,
+        "field_name": np.str_,
+        "language": np.str_,
+        "translation": np.str_,
+        "record_id": np.str_,
+        "record_sub_id": np.str_,
+        "field_value": np.str_,
+    }
+
+    @classmethod
+    def describe_all(cls) -> Dict[str, Dict[str, str]]:
+        """
+        Return the column names and dtype names of every table by file name, e.g. {"stops.txt": {"stop_id": "str"}}.
+        """
+        return {
+            f"{table}.txt": {column: pd.api.types.pandas_dtype(dtype).name for column, dtype in columns.items()}
+            for table, columns in vars(cls).items()
+            if isinstance(columns, dict) and not table.startswith("_")
+        }
 
 
 class GtfsFiles:
+    required_files: Tuple[str, ...] = (
+        "agency.txt",
+        "calendar_dates.txt",
+        "calendar.txt",
+        "feed_info.txt",
+        "routes.txt",
+        "stop_times.txt",
+        "stops.txt",
+        "trips.txt",
+    )
+    optional_files: Tuple[str, ...] = (
+        "areas.txt",
+        "booking_rules.txt",
+        "fare_attributes.txt",
+        "fare_rules.txt",
+        "frequencies.txt",
+        "levels.txt",
+        "pathways.txt",
+        "shapes.txt",
+        "stop_areas.txt",
+        "transfers.txt",
+        "translations.txt",
+    )
+
     # Required
     agency: Path
     calendar_dates: Path
     calendar: Path
-    feed_info: Path
     routes: Path
     stop_times: Path
     stops: Path
     trips: Path
+    # Required, but it may be left out if a feed_info.txt is synthesized for the output
+    _feed_info: Path | None = None
 
     # Optional - not complete
+    _areas: Path | None = None
+    _booking_rules: Path | None = None
+    _fare_attributes: Path | None = None
+    _fare_rules: Path | None = None
     _frequencies: Path | None = None
+    _levels: Path | None = None
+    _pathways: Path | None = None
     _shapes: Path | None = None
+    _stop_areas: Path | None = None
     _transfers: Path | None = None
+    _translations: Path | None = None
+
+    @property
+    def areas(self) -> Path:
+        if self._areas is None:
+            return Path("foo")
+        return self._areas
+
+    @property
+    def booking_rules(self) -> Path:
+        if self._booking_rules is None:
+            return Path("foo")
+        return self._booking_rules
+
+    @property
+    def fare_attributes(self) -> Path:
+        if self._fare_attributes is None:
+            return Path("foo")
+        return self._fare_attributes
+
+    @property
+    def fare_rules(self) -> Path:
+        if self._fare_rules is None:
+            return Path("foo")
+        return self._fare_rules
+
+    @property
+    def feed_info(self) -> Path:
+        if self._feed_info is None:
+            return Path("foo")
+        return self._feed_info
+
+    @feed_info.setter
+    def feed_info(self, file_path: Path) -> None:
+        self._feed_info = file_path
 
     @property
     def frequencies(self) -> Path:
@@ -157,60 +377,66 @@ class GtfsFiles:
             return Path("foo")
         return self._frequencies
 
+    @property
+    def levels(self) -> Path:
+        if self._levels is None:
+            return Path("foo")
+        return self._levels
+
+    @property
+    def pathways(self) -> Path:
+        if self._pathways is None:
+            return Path("foo")
+        return self._pathways
+
     @property
     def shapes(self) -> Path:
         if self._shapes is None:
             return Path("foo")
         return self._shapes
 
+    @property
+    def stop_areas(self) -> Path:
+        if self._stop_areas is None:
+            return Path("foo")
+        return self._stop_areas
+
     @property
     def transfers(self) -> Path:
         if self._transfers is None:
             return Path("foo")
         return self._transfers
 
-    def set_files(self, file_path: Path) -> None:
+    @property
+    def translations(self) -> Path:
+        if self._translations is None:
+            return Path("foo")
+        return self._translations
+
+    def set_files(self, file_path: Path) -> bool:
+        """
+        Register a GTFS table and return whether the file is one.
+        Only the canonical file names are GTFS tables, e.g. prefixed or backup copies are not.
+        """
         file_name: str = file_path.name
-        if "agency" in file_name:
-            self.agency = file_path
-        elif "calendar_dates" in file_name:
-            self.calendar_dates = file_path
-        elif "calendar" in file_name:
-            self.calendar = file_path
-        elif "feed_info" in file_name:
-            self.feed_info = file_path
-        elif "routes" in file_name:
-            self.routes = file_path
-        elif "stop_times" in file_name:
-            self.stop_times = file_path
-        elif "stops" in file_name:
-            self.stops = file_path
-        elif "trips" in file_name:
-            self.trips = file_path
-        elif "frequencies" in file_name:
-            self._frequencies = file_path
-        elif "shapes" in file_name:
-            self._shapes = file_path
-        elif "transfers" in file_name:
-            self._transfers = file_path
+        if file_name in self.required_files:
+            setattr(self, file_path.stem, file_path)
+        elif file_name in self.optional_files:
+            setattr(self, f"_{file_path.stem}", file_path)
         else:
-            logger.warn(f"Unknown file found: {file_path}")
+            return False
+        return True
 
-    def required_is_complete(self) -> bool:
-        if all(
-            [
-                self.agency,
-                self.calendar_dates,
-                self.calendar,
-                self.feed_info,
-                self.routes,
-                self.stop_times,
-                self.stops,
-                self.trips,
-            ]
-        ):
-            return True
-        return False
+    def required_is_complete(self, optional_files: Tuple[str, ...] = ()) -> bool:
+        """
+        Return whether all required files are set. optional_files are left out of the check.
+        """
+        file_name: str
+        for file_name in self.required_files:
+            file_path: Path | None = getattr(self, Path(file_name).stem, None)
+            if file_name not in optional_files and (file_path is None or not file_path.exists()):
+                return False
+        return True
 
 
 class GTFS:
@@ -219,21 +445,146 @@ class GTFS:
         input_object: Path,
         cpu_count: int | None = None,
         scheduler: str = "multiprocessing",
+        null_values: List[str] | None = None,
+        check_required_files: bool = True,
+        temp_dir: Path | None = None,
+        extract_files: Tuple[str, ...] | None = None,
+        fail_on_warning: bool = False,
+        io_retries: int = 0,
+        convert_tab_delimited: bool = True,
+        require_feed_info: bool = True,
     ) -> None:
+        """
+        Open the feed in a folder, zip or tar.gz file.
+        With check_required_files=False incomplete feeds can be opened to inspect or repair them.
+        require_feed_info=False accepts feeds without feed_info.txt, e.g. if one is synthesized for the output.
+        Tables of such feeds can still be read, but operations that need a missing file fail.
+        Archives are extracted below temp_dir, or the system temp directory if it isn't given.
+        extract_files limits the extraction to the given files, e.g. to read the calendar without unpacking
+        stop_times.txt. All files are extracted by default.
+        fail_on_warning raises on problems that are otherwise only logged, e.g. missing optional files.
+        io_retries retries reading an archive that failed with a transient I/O error.
+        convert_tab_delimited reads tab-delimited files as comma-delimited CSV, so they are written as such, too.
+        """
         self._input_folder: Path = input_object
         self._temporary_folder_context: Any[tempfile.TemporaryDirectory, None] = None
         self._gtfs_files: GtfsFiles = GtfsFiles()
         self._scheduler = scheduler
         self._cpu_count: int | None = cpu_count
+        # Read as missing values in addition to the pandas defaults
+        self._null_values: List[str] | None = null_values
+        self._temp_dir: Path | None = temp_dir
+        self._extract_files: Tuple[str, ...] | None = extract_files
+        self._fail_on_warning: bool = fail_on_warning
+        self._io_retries: int = io_retries
+        # Required files that may be missing, as they are written by other means
+        self._optional_required_files: Tuple[str, ...] = () if require_feed_info else ("feed_info.txt",)
 
         if input_object.is_file():
             input_object = self._extract_gtfs_file(input_object)
         if not input_object.exists():
             raise FileNotFoundError(errno.ENOENT, os.strerror(errno.ENOENT), input_object)
-        for test in input_object.glob("*.txt"):
-            self._gtfs_files.set_files(test)
-        if not self._gtfs_files.required_is_complete():
+        self._feed_folder: Path = self._find_feed_folder(input_object)
+        if convert_tab_delimited:
+            self._convert_tab_delimited_files()
+        for test in self._feed_folder.glob("*.txt"):
+            if not self._gtfs_files.set_files(test):
+                self._warn(f"Unknown file found: {test}")
+        if check_required_files and not self._required_is_complete():
             raise GtfsIncompleteException()
+        file_name: str
+        referring_file_name: str
+        for file_name, referring_file_name in CONDITIONALLY_REQUIRED_FILES.items():
+            if (
+                self._is_extracted(file_name)
+                and not getattr(self._gtfs_files, Path(file_name).stem).exists()
+                and getattr(self._gtfs_files, Path(referring_file_name).stem).exists()
+            ):
+                self._warn(f"{file_name} is missing, but {referring_file_name} refers to it")
+        for file_name in GtfsFiles.optional_files:
+            if self._is_extracted(file_name) and not getattr(self._gtfs_files, Path(file_name).stem).exists():
+                self._warn(f"Optional file {file_name} is missing", lenient_level=logging.INFO)
+
+    @staticmethod
+    def _find_feed_folder(folder: Path) -> Path:
+        """
+        Return the folder holding the GTFS files.
+        A manually unzipped feed often ends up in a single subfolder, so descend into it if the top level has no
+        GTFS files but exactly one subfolder has.
+        """
+        if any(folder.glob("*.txt")):
+            return folder
+        feed_folders: List[Path] = [
+            subfolder for subfolder in folder.iterdir() if subfolder.is_dir() and any(subfolder.glob("*.txt"))
+        ]
+        if len(feed_folders) == 1:
+            logger.info(f"Reading the GTFS files from the subfolder {feed_folders[0].name}.")
+            return feed_folders[0]
+        return folder
+
+    @staticmethod
+    def _is_tab_delimited(file_path: Path) -> bool:
+        # Only the header line is decoded, guessing the encoding would decode the whole file
+        header: str
+        with open(file_path, "rb") as fp:
+            if fp.read(2) in (codecs.BOM_UTF16_LE, codecs.BOM_UTF16_BE):
+                fp.seek(0)
+                header = fp.read(64 * 1024).decode("utf-16", errors="replace").partition("\n")[0]
+            else:
+                fp.seek(0)
+                # Tabs and commas are the same single byte in UTF-8 and the legacy encodings
+                header = fp.readline().decode("utf-8-sig", errors="replace")
+        return "\t" in header and "," not in header
+
+    def _convert_tab_delimited_files(self) -> None:
+        """
+        Rewrite the tab-delimited files of the feed as comma-delimited CSV, the only delimiter GTFS allows.
+        The files of an input folder are never changed, the feed is copied to a temporary folder first.
+        """
+        tab_delimited_files: List[Path] = [
+            file_path for file_path in sorted(self._feed_folder.glob("*.txt")) if self._is_tab_delimited(file_path)
+        ]
+        if not tab_delimited_files:
+            return
+        if self._temporary_folder_context is None:
+            self._check_writable(self._temp_dir if self._temp_dir else Path(tempfile.gettempdir()))
+            self._temporary_folder_context = tempfile.TemporaryDirectory(
+                prefix=f"{self._feed_folder.name}_", dir=self._temp_dir
+            )
+            copy_folder: Path = Path(self._temporary_folder_context.name)
+            file_path: Path
+            for file_path in self._feed_folder.iterdir():
+                if file_path.is_file():
+                    shutil.copyfile(file_path, copy_folder.joinpath(file_path.name))
+            self._feed_folder = copy_folder
+            tab_delimited_files = [copy_folder.joinpath(file_path.name) for file_path in tab_delimited_files]
+        for file_path in tab_delimited_files:
+            logger.warning(f"{file_path.name} is tab-delimited. It is read as comma-delimited CSV.")
+            encoding, _ = self._guess_encoding(file_path)
+            converted_path: Path = file_path.with_suffix(".csv")
+            with open(
+                file_path, "r", encoding="utf-8-sig" if encoding == "utf-8" else encoding, newline=""
+            ) as source, open(converted_path, "w", encoding="utf-8", newline="") as target:
+                csv.writer(target, lineterminator="\n").writerows(csv.reader(source, delimiter="\t"))
+            os.replace(converted_path, file_path)
+
+    def _warn(self, message: str, lenient_level: int = logging.WARNING) -> None:
+        """
+        Log a problem that doesn't stop the processing, or raise it with fail_on_warning.
+        """
+        if self._fail_on_warning:
+            raise WarningAsError(warning=message)
+        logger.log(lenient_level, message)
+
+    def _required_is_complete(self) -> bool:
+        if self._extract_files is None or not self._input_folder.is_file():
+            return self._gtfs_files.required_is_complete(self._optional_required_files)
+        # Only a part of the archive was extracted, so check the names of all its files
+        file_names: Set[str] = set(self.get_filenames()).union(self._optional_required_files)
+        return all(file_name in file_names for file_name in GtfsFiles.required_files)
+
+    def _is_extracted(self, file_name: str) -> bool:
+        return self._extract_files is None or Path(file_name).name in self._extract_files
 
     def close(self) -> None:
         if isinstance(self._temporary_folder_context, tempfile.TemporaryDirectory):
@@ -245,18 +596,844 @@ class GTFS:
     def __exit__(self, type: object, value: object, traceback: object) -> None:
         self.close()
 
+    @staticmethod
+    def _check_writable(directory: Path) -> None:
+        if not os.access(directory, os.W_OK | os.X_OK):
+            raise WorkingDirectoryNotWritable(directory=directory.__str__())
+
     def _extract_gtfs_file(self, input_file: Path) -> Path:
-        self._temporary_folder_context = tempfile.TemporaryDirectory()
+        # Fail before extracting anything instead of partway through the archive.
+        self._check_writable(self._temp_dir if self._temp_dir else Path(tempfile.gettempdir()))
+        # Every instance gets its own folder named after the input, so parallel runs sharing temp_dir don't collide.
+        self._temporary_folder_context = tempfile.TemporaryDirectory(
+            prefix=f"{input_file.name.split('.')[0]}_", dir=self._temp_dir
+        )
         extract_path: Path = Path(self._temporary_folder_context.name)
-        if not input_file.suffix == ".zip":
-            # TODO raise wrong file
-            logger.error("Input path is a file but not a .zip file. Exiting.")
-            raise Exception
-        logger.info("Input is a .zip file. It will be extracted to a temporary location.")
-        with zipfile.ZipFile(input_file, "r") as zip_ref:
-            zip_ref.extractall(extract_path)
+        self._with_retries(lambda: self._extract_archive(input_file, extract_path))
         return extract_path
 
+    def _extract_archive(self, input_file: Path, extract_path: Path) -> None:
+        archive_type: ArchiveType = ArchiveType.detect(input_file)
+        logger.info(f"Input is a .{archive_type.value} file. It will be extracted to a temporary location.")
+        # Nested prefixes like "feed/stops.txt" are flattened so every file lands in the extraction root.
+        if archive_type == ArchiveType.tar_gz:
+            with tarfile.open(input_file, "r:gz") as tar_ref:
+                tar_members: List[tarfile.TarInfo] = [
+                    member for member in self._tar_members(tar_ref) if self._is_extracted(member.name)
+                ]
+                self._check_unique_names([member.name for member in tar_members])
+                tar_member: tarfile.TarInfo
+                for tar_member in tar_members:
+                    tar_source: IO[bytes] | None = tar_ref.extractfile(tar_member)
+                    if tar_source is None:
+                        continue
+                    with tar_source, open(extract_path.joinpath(Path(tar_member.name).name), "wb") as target:
+                        shutil.copyfileobj(tar_source, target)
+            return
+        with zipfile.ZipFile(input_file, "r") as zip_ref:
+            zip_members: List[zipfile.ZipInfo] = [
+                member for member in self._zip_members(zip_ref) if self._is_extracted(member.filename)
+            ]
+            self._check_unique_names([member.filename for member in zip_members])
+            member: zipfile.ZipInfo
+            for member in zip_members:
+                target_path: Path = extract_path.joinpath(Path(member.filename).name)
+                try:
+                    with zip_ref.open(member) as source, open(target_path, "wb") as target:
+                        shutil.copyfileobj(source, target)
+                except UNREADABLE_ZIP_ENTRY_ERRORS as error:
+                    # A corrupt entry may fail only after a part of it was written
+                    target_path.unlink(missing_ok=True)
+                    # Without a GTFS table the feed would still be read, e.g. trips.txt without its shapes.txt
+                    if target_path.name in GtfsFiles.required_files + GtfsFiles.optional_files:
+                        raise UnreadableArchiveMember(file_name=member.filename, reason=str(error))
+                    self._skip_unreadable_entry(member, error)
+
+    @staticmethod
+    def _check_unique_names(member_names: List[str]) -> None:
+        """
+        Raise DuplicateArchiveMember if flattening the members would write two of them to the same file,
+        e.g. a/stops.txt and b/stops.txt.
+        """
+        members_by_name: Dict[str, List[str]] = {}
+        member_name: str
+        for member_name in member_names:
+            members_by_name.setdefault(Path(member_name).name, []).append(member_name)
+        file_name: str
+        members: List[str]
+        for file_name, members in members_by_name.items():
+            if len(members) > 1:
+                raise DuplicateArchiveMember(file_name=file_name, members=members)
+
+    def _with_retries(self, read_archive: Callable[[], T]) -> T:
+        """
+        Run an operation that reads the input archive and retry it up to io_retries times on transient I/O errors,
+        e.g. of network file systems. Invalid archives and missing files fail right away.
+        """
+        attempt: int = 0
+        while True:
+            try:
+                return read_archive()
+            except (FileNotFoundError, IsADirectoryError, PermissionError):
+                raise
+            except OSError as error:
+                if attempt >= self._io_retries:
+                    raise
+                delay: float = IO_RETRY_DELAY_SECONDS * 2**attempt
+                attempt += 1
+                logger.warning(
+                    f"Reading {self._input_folder} failed: {error}. Retry {attempt} of {self._io_retries} in {delay}s."
+                )
+                time.sleep(delay)
+
+    @staticmethod
+    def _zip_members(zip_ref: zipfile.ZipFile) -> List[zipfile.ZipInfo]:
+        return [member for member in zip_ref.infolist() if not member.is_dir()]
+
+    @staticmethod
+    def _tar_members(tar_ref: tarfile.TarFile) -> List[tarfile.TarInfo]:
+        # Only regular files, links and devices aren't part of a feed.
+        return [member for member in tar_ref.getmembers() if member.isfile()]
+
+    def get_filenames(self) -> List[str]:
+        """
+        Return the names of the files in the input object. Directory entries are skipped.
+        Zip entries with a corrupt header are skipped with a warning, so the other files can still be listed.
+        """
+        if self._input_folder.is_file():
+            return self._with_retries(self._archive_filenames)
+        return [file.name for file in self._feed_folder.iterdir() if file.is_file()]
+
+    def _archive_filenames(self) -> List[str]:
+        if ArchiveType.detect(self._input_folder) == ArchiveType.tar_gz:
+            with tarfile.open(self._input_folder, "r:gz") as tar_ref:
+                return [Path(member.name).name for member in self._tar_members(tar_ref)]
+        file_names: List[str] = []
+        with zipfile.ZipFile(self._input_folder, "r") as zip_ref:
+            member: zipfile.ZipInfo
+            for member in self._zip_members(zip_ref):
+                # Opening an entry checks its local header without reading the data
+                try:
+                    zip_ref.open(member).close()
+                except UNREADABLE_ZIP_ENTRY_ERRORS as error:
+                    self._skip_unreadable_entry(member, error)
+                    continue
+                file_names.append(Path(member.filename).name)
+        return file_names
+
+    def _skip_unreadable_entry(self, member: zipfile.ZipInfo, error: Exception) -> None:
+        self._warn(f"Skipping the unreadable entry {member.filename} of {self._input_folder.name}: {error}")
+
+    def content_fingerprint(self) -> str:
+        """
+        Return a SHA-256 hex digest over the names and contents of the GTFS files in file name order.
+        The digest doesn't depend on the archive format, compression or timestamps.
+        """
+        digest = hashlib.sha256()
+        file: Path
+        for file in sorted(self._feed_folder.glob("*.txt"), key=lambda path: path.name):
+            digest.update(f"{file.name}\0{file.stat().st_size}\0".encode("utf-8"))
+            with open(file, "rb") as fp:
+                for chunk in iter(lambda: fp.read(1024 * 1024), b""):
+                    digest.update(chunk)
+        return digest.hexdigest()
+
+    def _get_table_path(self, file_name: str) -> Path:
+        file_path: Any = getattr(self._gtfs_files, Path(file_name).stem, None)
+        if not isinstance(file_path, Path) or not file_path.exists():
+            raise GtfsFileNotFound(file_path=file_name)
+        return file_path
+
+    def read_table(self, file_name: str, usecols: List[str] | None = None) -> pd.DataFrame:
+        """
+        Read a GTFS file into a typed DataFrame. Columns in usecols that the file doesn't have are ignored.
+        """
+        file_path: Path = self._get_table_path(file_name)
+        dtype: Dict | str = getattr(GtfsDtypes, file_path.stem, "object")
+        return pd.read_csv(
+            file_path,
+            dtype=dtype,
+            usecols=(lambda column: column in usecols) if usecols else None,
+            na_values=self._null_values,
+            low_memory=False,
+        )
+
+    def table_batches(self, file_name: str, batch_rows: int) -> Iterator[pd.DataFrame]:
+        """
+        Iterate over a GTFS file in typed DataFrames of at most batch_rows rows.
+        The file is read lazily, so only one batch is held in memory at a time.
+        """
+        file_path: Path = self._get_table_path(file_name)
+        dtype: Dict | str = getattr(GtfsDtypes, file_path.stem, "object")
+        with pd.read_csv(
+            file_path, dtype=dtype, chunksize=batch_rows, na_values=self._null_values, low_memory=False
+        ) as reader:
+            yield from reader
+
+    def export_ndjson(self, output_folder: Path, batch_rows: int = NDJSON_BATCH_ROWS) -> List[Path]:
+        """
+        Write each GTFS file of the feed as <name>.ndjson with one JSON object per row.
+        The rows are typed with the GTFS dtypes, so numbers stay JSON numbers, ids stay strings and blanks become null.
+        The files are written batch by batch, so a large stop_times.txt is never held in memory at once.
+        """
+        os.makedirs(output_folder, exist_ok=True)
+        self._check_writable(output_folder)
+        known_files: Set[str] = set(GtfsFiles.required_files + GtfsFiles.optional_files)
+        files: List[Path] = []
+        file_path: Path
+        for file_path in sorted(self._feed_folder.glob("*.txt")):
+            if file_path.name not in known_files:
+                logger.info(f"Skip {file_path.name}. It is not a GTFS file.")
+                continue
+            output_path: Path = output_folder.joinpath(f"{file_path.stem}.ndjson")
+            with open(output_path, "w", encoding="utf-8") as fp:
+                batch: pd.DataFrame
+                for batch in self.table_batches(file_path.name, batch_rows):
+                    if batch.empty:
+                        continue
+                    # Older pandas versions don't end the last line with a line break
+                    fp.write(batch.to_json(orient="records", lines=True, force_ascii=False).rstrip("\n") + "\n")
+            files.append(output_path)
+        return files
+
+    def describe_table(self, file_name: str) -> pd.DataFrame:
+        """
+        Return the null count, distinct count and, for numeric and date columns, the min and max of each column.
+        The file is read lazily with the GTFS dtypes, so numeric columns compare by value.
+        """
+        file_path: Path = self._get_table_path(file_name)
+        dtype: Dict | str = getattr(GtfsDtypes, file_path.stem, "object")
+        table: ddf.DataFrame = ddf.read_csv(
+            file_path, dtype=dtype, na_values=self._null_values, low_memory=False, assume_missing=True
+        )
+        statistics: List = []
+        column: str
+        for column in table.columns:
+            series: ddf.Series = table[column]
+            ordered: bool = pd.api.types.is_numeric_dtype(series.dtype) or column.endswith("date")
+            statistics.append(
+                (
+                    series.isna().sum(),
+                    series.nunique(),
+                    series.dropna().min() if ordered else None,
+                    series.dropna().max() if ordered else None,
+                )
+            )
+        results: Tuple = ddf.compute(*statistics, scheduler=self._scheduler, num_workers=self._cpu_count)
+        return pd.DataFrame(
+            [
+                (column, str(table[column].dtype), *column_statistics)
+                for column, column_statistics in zip(table.columns, results)
+            ],
+            columns=["column", "dtype", "nulls", "distinct", "min", "max"],
+        )
+
+    @staticmethod
+    def _guess_encoding(file_path: Path) -> Tuple[str, bool]:
+        """
+        Return the encoding of a file and whether it starts with a byte order mark.
+        Files that aren't valid UTF-8 are taken to be Windows-1252, the usual legacy encoding of European feeds.
+        """
+        with open(file_path, "rb") as fp:
+            start: bytes = fp.read(4)
+            if start.startswith(codecs.BOM_UTF8):
+                return "utf-8", True
+            if start.startswith((codecs.BOM_UTF16_LE, codecs.BOM_UTF16_BE)):
+                return "utf-16", True
+            fp.seek(0)
+            decoder: codecs.IncrementalDecoder = codecs.getincrementaldecoder("utf-8")()
+            ascii_only: bool = True
+            try:
+                for chunk in iter(lambda: fp.read(1024 * 1024), b""):
+                    ascii_only = ascii_only and chunk.isascii()
+                    decoder.decode(chunk)
+                decoder.decode(b"", final=True)
+            except UnicodeDecodeError:
+                return "windows-1252", False
+        return "ascii" if ascii_only else "utf-8", False
+
+    def inspect_files(self) -> List[Dict[str, Any]]:
+        """
+        Report the delimiter, byte order mark, encoding, row count and columns of each file of the feed.
+        """
+        reports: List[Dict[str, Any]] = []
+        file_path: Path
+        for file_path in sorted(self._feed_folder.glob("*.txt")):
+            encoding, bom = self._guess_encoding(file_path)
+            # utf-8-sig strips the byte order mark from the first column name, utf-16 does so by itself
+            with open(
+                file_path, "r", encoding="utf-8-sig" if encoding == "utf-8" else encoding, errors="replace", newline=""
+            ) as fp:
+                header: str = fp.readline()
+                delimiter: str = ","
+                try:
+                    delimiter = csv.Sniffer().sniff(header, delimiters=",;\t|").delimiter
+                except csv.Error:
+                    pass
+                columns: List[str] = next(csv.reader([header], delimiter=delimiter), [])
+                rows: int = sum(1 for row in csv.reader(fp, delimiter=delimiter) if row)
+            reports.append(
+                {
+                    "file": file_path.name,
+                    "delimiter": delimiter,
+                    "bom": bom,
+                    "encoding": encoding,
+                    "rows": rows,
+                    "columns": columns,
+                }
+            )
+        return reports
+
+    def check_line_endings(self) -> List[str]:
+        """
+        Report the GTFS files that use \\r\\n or lone \\r line endings instead of \\n.
+        """
+        findings: List[str] = []
+        file_path: Path
+        for file_path in sorted(self._feed_folder.glob("*.txt")):
+            crlf: bool = False
+            lone_cr: bool = False
+            with open(file_path, "rb") as fp:
+                for line in fp:
+                    if line.endswith(b"\r\n"):
+                        crlf = True
+                        line = line[:-2]
+                    if b"\r" in line:
+                        lone_cr = True
+            if crlf:
+                findings.append(f"{file_path.name} contains \\r\\n line endings")
+            if lone_cr:
+                findings.append(f"{file_path.name} contains lone \\r line endings")
+        return findings
+
+    def feed_info_date_range(self) -> Tuple[datetime | None, datetime | None]:
+        """
+        Return the validity window declared in feed_info.txt. Dates that aren't set are returned as None.
+        """
+        if not self._gtfs_files.feed_info.exists():
+            return None, None
+        feed_info: pd.DataFrame = pd.read_csv(
+            self._gtfs_files.feed_info, dtype=GtfsDtypes.feed_info, na_values=self._null_values
+        )
+        dates: List[datetime | None] = []
+        for column in ("feed_start_date", "feed_end_date"):
+            if column in feed_info.columns and feed_info[column].notna().any():
+                dates.append(parse_date_from_str(feed_info[column].dropna().iloc[0]))
+            else:
+                dates.append(None)
+        return dates[0], dates[1]
+
+    def _service_dates(self, start_date: date | None = None, end_date: date | None = None) -> Dict[str, Set[date]]:
+        """
+        Expand calendar.txt by weekday and apply the calendar_dates.txt exceptions to get the active dates per service.
+        Only the dates from start_date to end_date are returned if they are given. Ranges that are still
+        MAX_SERVICE_DAYS or longer, e.g. open-ended services until 20991231, are only expanded for as many days.
+        """
+        service_dates: Dict[str, Set[date]] = {}
+        calendar: pd.DataFrame = self.read_table("calendar.txt")
+        calendar[list(WEEKDAYS)] = calendar[list(WEEKDAYS)].fillna(0)
+        service: Dict
+        for service in calendar.to_dict("records"):
+            active_weekdays: Set[int] = {index for index, weekday in enumerate(WEEKDAYS) if service[weekday] == 1}
+            dates: Set[date] = service_dates.setdefault(service["service_id"], set())
+            day: date = parse_date_from_str(service["start_date"]).date()
+            end_day: date = parse_date_from_str(service["end_date"]).date()
+            if start_date is not None:
+                day = max(day, start_date)
+            if end_date is not None:
+                end_day = min(end_day, end_date)
+            if (end_day - day).days >= MAX_SERVICE_DAYS:
+                end_day = day + timedelta(days=MAX_SERVICE_DAYS - 1)
+                logger.info(
+                    f"Service {service['service_id']} runs until {service['end_date']}. Only its dates until "
+                    f"{end_day.strftime('%Y%m%d')} are used."
+                )
+            while day <= end_day:
+                if day.weekday() in active_weekdays:
+                    dates.add(day)
+                day += timedelta(days=1)
+        calendar_dates: pd.DataFrame = self.read_table("calendar_dates.txt")
+        exception: Dict
+        for exception in calendar_dates.dropna(subset=["exception_type"]).to_dict("records"):
+            dates = service_dates.setdefault(exception["service_id"], set())
+            exception_day: date = parse_date_from_str(exception["date"]).date()
+            if (start_date is not None and exception_day < start_date) or (
+                end_date is not None and exception_day > end_date
+            ):
+                continue
+            if exception["exception_type"] == 1:
+                dates.add(exception_day)
+            elif exception["exception_type"] == 2:
+                dates.discard(exception_day)
+        return service_dates
+
+    def _active_service_ids(self, day: date) -> Set[str]:
+        """
+        Return the service_ids active on day. Only day is looked up, so open-ended services are no problem.
+        """
+        day_string: str = day.strftime("%Y%m%d")
+        calendar: pd.DataFrame = self.read_table("calendar.txt")
+        running: pd.Series = (
+            (calendar[WEEKDAYS[day.weekday()]].fillna(0) == 1)
+            & (calendar["start_date"] <= day_string)
+            & (calendar["end_date"] >= day_string)
+        )
+        service_ids: Set[str] = set(calendar.loc[running, "service_id"])
+        calendar_dates: pd.DataFrame = self.read_table("calendar_dates.txt")
+        exceptions: pd.DataFrame = calendar_dates[calendar_dates["date"] == day_string].dropna(
+            subset=["exception_type"]
+        )
+        service_ids.update(exceptions.loc[exceptions["exception_type"] == 1, "service_id"])
+        service_ids.difference_update(exceptions.loc[exceptions["exception_type"] == 2, "service_id"])
+        return service_ids
+
+    def service_calendar(self) -> Dict[str, List[date]]:
+        """
+        Return the sorted active dates of each service_id.
+        calendar.txt is expanded by weekday over its range and the calendar_dates.txt exceptions are applied.
+        Services with a range of MAX_SERVICE_DAYS or more are only listed for as many days from their start_date.
+        """
+        return {service_id: sorted(dates) for service_id, dates in self._service_dates().items()}
+
+    def weekday_coverage(self) -> List[int]:
+        """
+        Count the service days per weekday from Monday to Sunday over all services.
+        """
+        coverage: List[int] = [0] * len(WEEKDAYS)
+        dates: Set[date]
+        for dates in self._service_dates().values():
+            day: date
+            for day in dates:
+                coverage[day.weekday()] += 1
+        return coverage
+
+    def headways(self, day: date) -> pd.DataFrame:
+        """
+        Return the minimum, median and maximum seconds between consecutive departures of the trips running on day,
+        per route_id, direction_id and the stop_id the trips start at. Departures at the same time count once.
+        """
+        service_ids: Set[str] = self._active_service_ids(day)
+        trips: pd.DataFrame = self.read_table(
+            "trips.txt", usecols=["route_id", "service_id", "trip_id", "direction_id"]
+        )
+        if "direction_id" not in trips.columns:
+            trips["direction_id"] = pd.Series(pd.NA, index=trips.index, dtype="Int64")
+        trips = trips[trips["service_id"].isin(service_ids)]
+        stop_times: ddf.DataFrame = ddf.read_csv(
+            self._gtfs_files.stop_times,
+            usecols=["trip_id", "stop_id", "stop_sequence", "arrival_time", "departure_time"],
+            dtype=GtfsDtypes.stop_times,
+            na_values=self._null_values,
+            low_memory=False,
+        )
+        trip_stop_times: pd.DataFrame = stop_times[stop_times["trip_id"].isin(trips["trip_id"].tolist())].compute(
+            scheduler=self._scheduler, num_workers=self._cpu_count
+        )
+        first_stops: pd.DataFrame = trip_stop_times.sort_values(["trip_id", "stop_sequence"]).drop_duplicates(
+            "trip_id"
+        )
+        first_stops = first_stops.assign(
+            departure=time_to_seconds(first_stops["departure_time"].fillna(first_stops["arrival_time"]))
+        ).merge(trips, on="trip_id")
+        columns: List[str] = ["route_id", "direction_id", "stop_id"]
+        departures: pd.DataFrame = (
+            first_stops.dropna(subset=["departure"])
+            .drop_duplicates([*columns, "departure"])
+            .sort_values([*columns, "departure"])
+        )
+        departures["headway"] = departures.groupby(columns, dropna=False)["departure"].diff()
+        return (
+            departures.dropna(subset=["headway"])
+            .groupby(columns, dropna=False)["headway"]
+            .agg(min_headway="min", median_headway="median", max_headway="max")
+            .reset_index()
+        )
+
+    def busiest_stops(self, n: int, day: date | None = None) -> pd.DataFrame:
+        """
+        Return the n stops with the most stop_times, with their stop_name, busiest first.
+        With day only the trips of the services active on that day are counted. Ties are ordered by stop_id.
+        """
+        stop_times: ddf.DataFrame = ddf.read_csv(
+            self._gtfs_files.stop_times,
+            usecols=["trip_id", "stop_id"],
+            dtype=GtfsDtypes.stop_times,
+            na_values=self._null_values,
+            low_memory=False,
+        )
+        if day is not None:
+            service_ids: Set[str] = self._active_service_ids(day)
+            trips: pd.DataFrame = self.read_table("trips.txt", usecols=["service_id", "trip_id"])
+            trips = trips[trips["service_id"].isin(service_ids)]
+            stop_times = stop_times[stop_times["trip_id"].isin(trips["trip_id"].tolist())]
+        counts: pd.DataFrame = (
+            stop_times["stop_id"]
+            .value_counts()
+            .compute(scheduler=self._scheduler, num_workers=self._cpu_count)
+            .rename_axis("stop_id")
+            .rename("stop_times")
+            .reset_index()
+        )
+        stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "stop_name"])
+        return (
+            counts.sort_values(["stop_times", "stop_id"], ascending=[False, True])
+            .head(n)
+            .merge(stops, on="stop_id", how="left")[["stop_id", "stop_name", "stop_times"]]
+        )
+
+    def estimate_extract_size(
+        self,
+        start_date: date | None = None,
+        end_date: date | None = None,
+        bbox: Bbox | None = None,
+        quoting: int = csv.QUOTE_ALL,
+    ) -> pd.DataFrame:
+        """
+        Estimate the rows and bytes of each file of an extraction by date range and/or bbox without extracting it.
+        The kept trips and their stop_times are counted lazily, the other tables are scaled by the share of kept trips
+        and feed_info.txt is kept whole. The bytes are the rows times the average size of a sample of input rows,
+        written with the given quoting.
+        """
+        trips: pd.DataFrame = self.read_table("trips.txt", usecols=["trip_id", "service_id"])
+        if start_date is not None and end_date is not None:
+            service_ids: Set[str] = {
+                service_id for service_id, dates in self._service_dates(start_date, end_date).items() if dates
+            }
+            trips = trips[trips["service_id"].isin(service_ids)]
+        stop_times: ddf.DataFrame = ddf.read_csv(
+            self._gtfs_files.stop_times,
+            usecols=["trip_id", "stop_id"],
+            dtype=GtfsDtypes.stop_times,
+            na_values=self._null_values,
+            low_memory=False,
+        )
+        if bbox is not None:
+            stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "stop_lat", "stop_lon"])
+            in_bbox: pd.Series = bbox.contains_points(stops["stop_lat"], stops["stop_lon"])
+            stop_ids: List[str] = stops.loc[in_bbox, "stop_id"].tolist()
+            trip_ids_in_bbox: pd.Series = (
+                stop_times.loc[stop_times["stop_id"].isin(stop_ids), "trip_id"]
+                .unique()
+                .compute(scheduler=self._scheduler, num_workers=self._cpu_count)
+            )
+            trips = trips[trips["trip_id"].isin(trip_ids_in_bbox)]
+        stop_time_counts: pd.Series = (
+            stop_times["trip_id"].value_counts().compute(scheduler=self._scheduler, num_workers=self._cpu_count)
+        )
+        trip_count: int = len(self.read_table("trips.txt", usecols=["trip_id"]))
+        trip_share: float = len(trips) / trip_count if trip_count else 0.0
+
+        estimates: List[Dict[str, Any]] = []
+        file_path: Path
+        for file_path in sorted(self._feed_folder.glob("*.txt")):
+            rows: int
+            if file_path.name == "trips.txt":
+                rows = len(trips)
+            elif file_path.name == "stop_times.txt":
+                rows = int(stop_time_counts.reindex(trips["trip_id"]).fillna(0).sum())
+            else:
+                input_rows: int = sum(len(batch) for batch in self.table_batches(file_path.name, 100000))
+                rows = input_rows if file_path.name == "feed_info.txt" else int(np.ceil(input_rows * trip_share))
+            sample: pd.DataFrame = pd.read_csv(
+                file_path, dtype=str, nrows=ESTIMATE_SAMPLE_ROWS, na_values=self._null_values, low_memory=False
+            )
+            header_bytes: int = len(sample.head(0).to_csv(index=False, quoting=quoting).encode())
+            sample_bytes: int = len(sample.to_csv(index=False, header=False, quoting=quoting).encode())
+            row_bytes: float = sample_bytes / len(sample) if len(sample) else 0.0
+            estimates.append(
+                {"file": file_path.name, "rows": rows, "bytes": int(round(header_bytes + rows * row_bytes))}
+            )
+        return pd.DataFrame(estimates, columns=["file", "rows", "bytes"])
+
+    def route_bounds(self) -> pd.DataFrame:
+        """
+        Return the minimum and maximum stop_lat and stop_lon of the stops served by each route.
+        Stops with 0/0 placeholder coordinates are left out.
+        """
+        trip_routes: pd.DataFrame = self.read_table("trips.txt", usecols=["trip_id", "route_id"])
+        stop_times: ddf.DataFrame = ddf.read_csv(
+            self._gtfs_files.stop_times,
+            usecols=["trip_id", "stop_id"],
+            dtype=GtfsDtypes.stop_times,
+            na_values=self._null_values,
+            low_memory=False,
+        )
+        route_stops: pd.DataFrame = (
+            stop_times.merge(trip_routes, on="trip_id")[["route_id", "stop_id"]]
+            .drop_duplicates()
+            .compute(scheduler=self._scheduler, num_workers=self._cpu_count)
+        )
+        stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "stop_lat", "stop_lon"])
+        stops = stops[~((stops["stop_lat"] == 0) & (stops["stop_lon"] == 0))]
+        return (
+            route_stops.merge(stops, on="stop_id")
+            .groupby("route_id")
+            .agg(
+                min_lat=("stop_lat", "min"),
+                max_lat=("stop_lat", "max"),
+                min_lon=("stop_lon", "min"),
+                max_lon=("stop_lon", "max"),
+            )
+            .dropna()
+            .reset_index()
+        )
+
+    def route_endpoints(self) -> pd.DataFrame:
+        """
+        Return the names of the first and last stop of the trips of each route_id and direction_id.
+        Routes with several patterns report their most common pair of endpoints, trips counts the trips running it.
+        """
+        trips: pd.DataFrame = self.read_table("trips.txt", usecols=["route_id", "trip_id", "direction_id"])
+        if "direction_id" not in trips.columns:
+            trips["direction_id"] = pd.Series(pd.NA, index=trips.index, dtype="Int64")
+        stop_times: ddf.DataFrame = ddf.read_csv(
+            self._gtfs_files.stop_times,
+            usecols=["trip_id", "stop_id", "stop_sequence"],
+            dtype=GtfsDtypes.stop_times,
+            na_values=self._null_values,
+            low_memory=False,
+        )
+        ordered: pd.DataFrame = stop_times.compute(scheduler=self._scheduler, num_workers=self._cpu_count).sort_values(
+            ["trip_id", "stop_sequence"]
+        )
+        first_stops: pd.Series = ordered.drop_duplicates("trip_id").set_index("trip_id")["stop_id"]
+        last_stops: pd.Series = ordered.drop_duplicates("trip_id", keep="last").set_index("trip_id")["stop_id"]
+        stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "stop_name"])
+        stop_names: pd.Series = stops.set_index("stop_id")["stop_name"]
+        endpoints: pd.DataFrame = pd.DataFrame(
+            {"first_stop": first_stops.map(stop_names), "last_stop": last_stops.map(stop_names)}
+        )
+        columns: List[str] = ["route_id", "direction_id"]
+        patterns: pd.DataFrame = (
+            trips.merge(endpoints, left_on="trip_id", right_index=True)
+            .groupby([*columns, "first_stop", "last_stop"], dropna=False)
+            .size()
+            .rename("trips")
+            .reset_index()
+        )
+        return (
+            patterns.sort_values([*columns, "trips"], ascending=[True, True, False], kind="stable")
+            .drop_duplicates(columns)
+            .reset_index(drop=True)
+        )
+
+    def find_clustered_stops(self, meters: float) -> List[List[str]]:
+        """
+        Return the groups of stops that lie within meters of each other, e.g. to find duplicates with different ids.
+        Stops are grouped transitively and only groups with more than one stop are returned.
+        """
+        stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "stop_lat", "stop_lon"]).dropna()
+        stops = stops[~((stops["stop_lat"] == 0) & (stops["stop_lon"] == 0))].reset_index(drop=True)
+        if stops.empty:
+            return []
+        # Sort the stops into a grid of cells that are at least meters wide, so only stops of neighbouring cells
+        # need to be compared. A longitude degree is shortest at the stop closest to a pole.
+        cell_lat: float = np.degrees(max(meters, 1e-3) / EARTH_RADIUS_METERS)
+        cell_lon: float = cell_lat / max(np.cos(np.radians(min(stops["stop_lat"].abs().max(), 89.0))), 1e-6)
+        stops["cell_x"] = np.floor(stops["stop_lon"] / cell_lon).astype(np.int64)
+        stops["cell_y"] = np.floor(stops["stop_lat"] / cell_lat).astype(np.int64)
+        stops["position"] = stops.index
+
+        pairs: List[pd.DataFrame] = []
+        offset_x: int
+        offset_y: int
+        for offset_x in (-1, 0, 1):
+            for offset_y in (-1, 0, 1):
+                neighbours: pd.DataFrame = stops.assign(
+                    cell_x=stops["cell_x"] + offset_x, cell_y=stops["cell_y"] + offset_y
+                )
+                candidates: pd.DataFrame = stops.merge(neighbours, on=["cell_x", "cell_y"], suffixes=("", "_other"))
+                candidates = candidates[candidates["position"] < candidates["position_other"]]
+                distances: pd.Series = haversine_meters(
+                    candidates["stop_lat"],
+                    candidates["stop_lon"],
+                    candidates["stop_lat_other"],
+                    candidates["stop_lon_other"],
+                )
+                pairs.append(candidates.loc[distances <= meters, ["position", "position_other"]])
+
+        # Union-find over the close pairs, each stop points towards the root of its cluster
+        parents: List[int] = list(range(len(stops)))
+
+        def find(index: int) -> int:
+            while parents[index] != index:
+                parents[index] = parents[parents[index]]
+                index = parents[index]
+            return index
+
+        first: int
+        second: int
+        for first, second in pd.concat(pairs).values:
+            parents[find(first)] = find(second)
+        clusters: Dict[int, List[str]] = {}
+        position: int
+        stop_id: str
+        for position, stop_id in enumerate(stops["stop_id"]):
+            clusters.setdefault(find(position), []).append(stop_id)
+        return sorted(sorted(cluster) for cluster in clusters.values() if len(cluster) > 1)
+
+    def find_duplicate_trips(self) -> List[List[str]]:
+        """
+        Return the groups of trips with the same route_id, service_id and the same ordered stop_id, arrival_time and
+        departure_time of all their stop_times. Only groups with more than one trip are returned.
+        """
+        stop_times: pd.DataFrame = self.read_table(
+            "stop_times.txt", usecols=["trip_id", "stop_sequence", "stop_id", "arrival_time", "departure_time"]
+        ).sort_values(["trip_id", "stop_sequence"])
+        stops: pd.Series = (
+            stop_times["stop_id"].fillna("")
+            + ","
+            + stop_times["arrival_time"].fillna("")
+            + ","
+            + stop_times["departure_time"].fillna("")
+        )
+        patterns: pd.Series = stops.groupby(stop_times["trip_id"]).agg(
+            lambda trip_stops: hashlib.sha256("\n".join(trip_stops).encode()).hexdigest()
+        )
+        trips: pd.DataFrame = self.read_table("trips.txt", usecols=["route_id", "service_id", "trip_id"])
+        trips = trips.assign(pattern=trips["trip_id"].map(patterns)).dropna(subset=["pattern"])
+        groups: pd.Series = trips.groupby(["route_id", "service_id", "pattern"], dropna=False)["trip_id"].agg(list)
+        return sorted(sorted(group) for group in groups if len(group) > 1)
+
+    @staticmethod
+    def _path_lengths(points: pd.DataFrame, group: str, lat: str, lon: str) -> pd.Series:
+        # points must be ordered along each path
+        previous: pd.DataFrame = points.groupby(group, sort=False)[[lat, lon]].shift()
+        segments: pd.Series = haversine_meters(previous[lat], previous[lon], points[lat], points[lon])
+        return segments.groupby(points[group]).sum()
+
+    def trip_distances(self) -> pd.DataFrame:
+        """
+        Return the distance in meters of each trip and the largest shape_dist_traveled of its shape.
+        Trips with a shape use the length of the shape. The other trips, and those whose shape has no length, use the
+        sum of the distances between their consecutive stops. shape_dist_traveled is kept in the units of the feed,
+        which GTFS leaves open, so it is only returned next to the distance.
+        """
+        trips: pd.DataFrame = self.read_table("trips.txt", usecols=["trip_id", "shape_id"])
+        if "shape_id" not in trips.columns:
+            trips["shape_id"] = pd.Series(pd.NA, index=trips.index, dtype=object)
+        shape_lengths: pd.Series = pd.Series(dtype=np.float64)
+        shape_distances: pd.Series = pd.Series(dtype=np.float64)
+        if self._gtfs_files.shapes.exists():
+            shapes: pd.DataFrame = self.read_table("shapes.txt").sort_values(["shape_id", "shape_pt_sequence"])
+            shape_lengths = self._path_lengths(shapes, "shape_id", "shape_pt_lat", "shape_pt_lon")
+            # All points of a shape may share one coordinate, which says nothing about the distance
+            shape_lengths = shape_lengths[shape_lengths > 0]
+            if "shape_dist_traveled" in shapes.columns:
+                shape_distances = shapes.groupby("shape_id")["shape_dist_traveled"].max()
+        distances: pd.Series = trips["shape_id"].map(shape_lengths)
+
+        shapeless_trip_ids: List[str] = trips.loc[distances.isna(), "trip_id"].tolist()
+        if shapeless_trip_ids:
+            stop_times: ddf.DataFrame = ddf.read_csv(
+                self._gtfs_files.stop_times,
+                usecols=["trip_id", "stop_id", "stop_sequence"],
+                dtype=GtfsDtypes.stop_times,
+                na_values=self._null_values,
+                low_memory=False,
+            )
+            trip_stops: pd.DataFrame = stop_times[stop_times["trip_id"].isin(shapeless_trip_ids)].compute(
+                scheduler=self._scheduler, num_workers=self._cpu_count
+            )
+            stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "stop_lat", "stop_lon"])
+            trip_stops = trip_stops.merge(stops, on="stop_id").sort_values(["trip_id", "stop_sequence"])
+            stop_lengths: pd.Series = self._path_lengths(trip_stops, "trip_id", "stop_lat", "stop_lon")
+            distances = distances.fillna(trips["trip_id"].map(stop_lengths))
+        return (
+            pd.DataFrame(
+                {
+                    "trip_id": trips["trip_id"],
+                    "distance_meters": distances,
+                    "shape_dist_traveled": trips["shape_id"].map(shape_distances),
+                }
+            )
+            .dropna(subset=["distance_meters"])
+            .reset_index(drop=True)
+        )
+
+    def trip_durations(self) -> pd.DataFrame:
+        """
+        Return the number of trips and the minimum, mean and maximum duration in seconds of the trips of each route.
+        A trip lasts from the arrival at its first stop to the departure from its last stop. Trips without a time at
+        either end are skipped with a warning.
+        """
+        stop_times: ddf.DataFrame = ddf.read_csv(
+            self._gtfs_files.stop_times,
+            usecols=["trip_id", "stop_sequence", "arrival_time", "departure_time"],
+            dtype=GtfsDtypes.stop_times,
+            na_values=self._null_values,
+            low_memory=False,
+        )
+        ordered: pd.DataFrame = stop_times.compute(scheduler=self._scheduler, num_workers=self._cpu_count).sort_values(
+            ["trip_id", "stop_sequence"]
+        )
+        first_stops: pd.DataFrame = ordered.drop_duplicates("trip_id").set_index("trip_id")
+        last_stops: pd.DataFrame = ordered.drop_duplicates("trip_id", keep="last").set_index("trip_id")
+        starts: pd.Series = time_to_seconds(first_stops["arrival_time"].fillna(first_stops["departure_time"]))
+        ends: pd.Series = time_to_seconds(last_stops["departure_time"].fillna(last_stops["arrival_time"]))
+        durations: pd.Series = (ends - starts).rename("duration")
+        missing: int = durations.isna().sum()
+        if missing:
+            self._warn(f"{missing} trips without a time at their first or last stop are skipped")
+
+        trips: pd.DataFrame = self.read_table("trips.txt", usecols=["route_id", "trip_id"])
+        return (
+            trips.merge(durations.dropna(), left_on="trip_id", right_index=True)
+            .groupby("route_id")["duration"]
+            .agg(trips="count", min_duration="min", mean_duration="mean", max_duration="max")
+            .reset_index()
+        )
+
+    def check_trip_shape_alignment(self, max_meters: float) -> List[str]:
+        """
+        Return the trips with a shape that have a stop farther than max_meters from the nearest vertex of the shape.
+        Each stop is only measured once per shape, however many trips share the shape.
+        Trips whose shape is missing from shapes.txt and stops without coordinates are skipped.
+        """
+        trips: pd.DataFrame = self.read_table("trips.txt", usecols=["trip_id", "shape_id"])
+        if "shape_id" not in trips.columns or not self._gtfs_files.shapes.exists():
+            return []
+        shapes: pd.DataFrame = self.read_table(
+            "shapes.txt", usecols=["shape_id", "shape_pt_lat", "shape_pt_lon"]
+        ).dropna()
+        trips = trips[trips["shape_id"].isin(shapes["shape_id"])]
+        if trips.empty:
+            return []
+        stop_times: ddf.DataFrame = ddf.read_csv(
+            self._gtfs_files.stop_times,
+            usecols=["trip_id", "stop_id"],
+            dtype=GtfsDtypes.stop_times,
+            na_values=self._null_values,
+            low_memory=False,
+        )
+        trip_stops: pd.DataFrame = (
+            stop_times[stop_times["trip_id"].isin(trips["trip_id"])]
+            .compute(scheduler=self._scheduler, num_workers=self._cpu_count)
+            .drop_duplicates()
+            .merge(trips, on="trip_id")
+        )
+        stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "stop_lat", "stop_lon"]).dropna()
+        shape_stops: pd.DataFrame = trip_stops[["shape_id", "stop_id"]].drop_duplicates().merge(stops, on="stop_id")
+
+        far_stops: List[pd.DataFrame] = []
+        shape_id: str
+        vertices: pd.DataFrame
+        for shape_id, vertices in shapes.groupby("shape_id"):
+            candidates: pd.DataFrame = shape_stops[shape_stops["shape_id"] == shape_id]
+            if candidates.empty:
+                continue
+            # Pair the stops with the vertices one shape at a time, so memory is bounded by the largest shape
+            pairs: pd.DataFrame = candidates.merge(vertices.drop(columns="shape_id"), how="cross")
+            distances: pd.Series = haversine_meters(
+                pairs["stop_lat"], pairs["stop_lon"], pairs["shape_pt_lat"], pairs["shape_pt_lon"]
+            )
+            nearest: pd.Series = distances.groupby(pairs["stop_id"]).min()
+            far_stops.append(candidates[candidates["stop_id"].map(nearest) > max_meters][["shape_id", "stop_id"]])
+        if not far_stops:
+            return []
+        misaligned: pd.DataFrame = trip_stops.merge(pd.concat(far_stops), on=["shape_id", "stop_id"])
+        return sorted(misaligned["trip_id"].unique())
+
     def service_date_range(self) -> Tuple:
         """
         Return the date range of the data set.
@@ -267,6 +1444,7 @@ class GTFS:
             usecols=["start_date", "end_date"],
             parse_dates=["start_date", "end_date"],
             date_parser=parse_date_from_str,
+            na_values=self._null_values,
             low_memory=False,
         )
         xmin, xmax = ddf.compute(
@@ -275,3 +1453,17 @@ class GTFS:
             num_workers=self._cpu_count,
         )
         return xmin.strftime("%Y-%m-%d %H:%M:%S"), xmax.strftime("%Y-%m-%d %H:%M:%S")
+
+    def feed_summary(self) -> Dict[str, Any]:
+        """
+        Return the service date window and the number of agencies, routes, trips and stops of the feed.
+        """
+        service_start, service_end = self.service_date_range()
+        return {
+            "service_start": service_start,
+            "service_end": service_end,
+            "agencies": len(self.read_table("agency.txt", usecols=["agency_name"])),
+            "routes": len(self.read_table("routes.txt", usecols=["route_id"])),
+            "trips": len(self.read_table("trips.txt", usecols=["trip_id"])),
+            "stops": len(self.read_table("stops.txt", usecols=["stop_id"])),
+        }
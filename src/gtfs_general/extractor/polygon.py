@@ -0,0 +1,88 @@
+from __future__ import annotations
+
+import json
+from pathlib import Path
+from typing import Any, Dict, List
+
+import numpy as np
+import pandas as pd
+
+from gtfs_general.exceptions.extractor_exceptions import InvalidPolygon
+
+
+class Polygon:
+    # Each polygon is a list of rings of lon/lat positions, the first ring is the outer boundary, the others holes
+    _polygons: List[List[np.ndarray]]
+
+    def __init__(self, polygons: List[List[np.ndarray]]) -> None:
+        super().__init__()
+        self._polygons = polygons
+
+    @staticmethod
+    def from_geojson(file_path: Path) -> Polygon:
+        """
+        Read the Polygon and MultiPolygon geometries of a GeoJSON geometry, Feature or FeatureCollection.
+        Other geometries are ignored, a file without any polygon raises InvalidPolygon.
+        """
+        try:
+            with open(file_path, "r") as fp:
+                data: Dict[str, Any] = json.load(fp)
+        except json.JSONDecodeError as error:
+            raise InvalidPolygon(file_path=file_path.__str__(), reason=f"invalid JSON, {error}")
+        geometries: List[Dict[str, Any]]
+        if data.get("type") == "FeatureCollection":
+            geometries = [feature.get("geometry") or {} for feature in data.get("features", [])]
+        elif data.get("type") == "Feature":
+            geometries = [data.get("geometry") or {}]
+        else:
+            geometries = [data]
+
+        polygons: List[List[np.ndarray]] = []
+        geometry: Dict[str, Any]
+        for geometry in geometries:
+            coordinates: List = geometry.get("coordinates", [])
+            if geometry.get("type") == "Polygon":
+                coordinates = [coordinates]
+            elif geometry.get("type") != "MultiPolygon":
+                continue
+            polygon: List
+            for polygon in coordinates:
+                try:
+                    rings: List[np.ndarray] = [np.asarray(ring, dtype=float)[:, :2] for ring in polygon]
+                except (IndexError, TypeError, ValueError):
+                    raise InvalidPolygon(file_path=file_path.__str__(), reason="rings must be lists of positions")
+                if not rings or any(len(ring) < 4 for ring in rings):
+                    raise InvalidPolygon(
+                        file_path=file_path.__str__(), reason="every ring needs at least four positions"
+                    )
+                polygons.append(rings)
+        if not polygons:
+            raise InvalidPolygon(file_path=file_path.__str__(), reason="it contains no Polygon or MultiPolygon")
+        return Polygon(polygons)
+
+    @staticmethod
+    def _ring_contains(ring: np.ndarray, lat: np.ndarray, lon: np.ndarray) -> np.ndarray:
+        # Ray casting: a point is inside if a ray towards increasing longitude crosses the ring an odd number of times
+        inside: np.ndarray = np.zeros(len(lat), dtype=bool)
+        start: np.ndarray
+        end: np.ndarray
+        for start, end in zip(ring, np.roll(ring, -1, axis=0)):
+            crosses: np.ndarray = (start[1] > lat) != (end[1] > lat)
+            with np.errstate(divide="ignore", invalid="ignore"):
+                crossing_lon: np.ndarray = start[0] + (lat - start[1]) * (end[0] - start[0]) / (end[1] - start[1])
+            inside ^= crosses & (lon < crossing_lon)
+        return inside
+
+    def contains_points(self, lat: pd.Series, lon: pd.Series) -> pd.Series:
+        lat_values: np.ndarray = lat.to_numpy(dtype=float)
+        lon_values: np.ndarray = lon.to_numpy(dtype=float)
+        contained: np.ndarray = np.zeros(len(lat_values), dtype=bool)
+        rings: List[np.ndarray]
+        for rings in self._polygons:
+            # Holes flip the points inside them back to outside
+            in_polygon: np.ndarray = np.zeros(len(lat_values), dtype=bool)
+            ring: np.ndarray
+            for ring in rings:
+                in_polygon ^= self._ring_contains(ring, lat_values, lon_values)
+            contained |= in_polygon
+        return pd.Series(contained, index=lat.index)
@@ -1,5 +1,80 @@
 from datetime import datetime
+from enum import Enum
+from typing import List, Set
+
+import numpy as np
+import pandas as pd
+
+# Mean earth radius
+EARTH_RADIUS_METERS: float = 6371008.8
+
+
+class MembershipStrategy(str, Enum):
+    isin = "isin"
+    join = "join"
 
 
 def parse_date_from_str(x: str) -> datetime:
     return datetime.strptime(x, "%Y%m%d")
+
+
+def time_to_seconds(times: pd.Series) -> pd.Series:
+    """
+    Seconds since the start of the service day of GTFS HH:MM:SS times. Hours past 24 are kept, so trips running
+    past midnight stay ordered after the ones of the evening. Single digit hours like 5:00:00 are accepted.
+    """
+    parts: pd.DataFrame = times.str.split(":", expand=True).reindex(columns=range(3)).astype(float)
+    return parts[0] * 3600 + parts[1] * 60 + parts[2]
+
+
+def seconds_to_time(seconds: pd.Series) -> pd.Series:
+    """
+    GTFS HH:MM:SS times of seconds since the start of the service day, rounded to whole seconds.
+    Missing seconds stay missing.
+    """
+    whole: pd.Series = seconds.round().astype("Int64")
+    time: pd.Series = (
+        (whole // 3600).astype(str).str.zfill(2)
+        + ":"
+        + (whole % 3600 // 60).astype(str).str.zfill(2)
+        + ":"
+        + (whole % 60).astype(str).str.zfill(2)
+    )
+    return time.where(whole.notna(), pd.NA)
+
+
+def format_bytes(size: float) -> str:
+    """
+    Human readable size in binary units, e.g. 1.5 MiB.
+    """
+    unit: str
+    for unit in ("B", "KiB", "MiB", "GiB"):
+        if size < 1024:
+            return f"{size:.0f} {unit}" if unit == "B" else f"{size:.1f} {unit}"
+        size /= 1024
+    return f"{size:.1f} TiB"
+
+
+def haversine_meters(lat1: pd.Series, lon1: pd.Series, lat2: pd.Series, lon2: pd.Series) -> pd.Series:
+    """
+    Great circle distance in meters between the points of two coordinate series.
+    """
+    lat1, lon1, lat2, lon2 = (np.radians(series) for series in (lat1, lon1, lat2, lon2))
+    a: pd.Series = np.sin((lat2 - lat1) / 2) ** 2 + np.cos(lat1) * np.cos(lat2) * np.sin((lon2 - lon1) / 2) ** 2
+    return 2 * EARTH_RADIUS_METERS * np.arcsin(np.sqrt(a))
+
+
+def filter_rows_by_ids(
+    rows: pd.DataFrame, ids: Set, columns: List, strategy: MembershipStrategy = MembershipStrategy.isin
+) -> pd.DataFrame:
+    """
+    Keep the rows whose values in all given columns are part of ids.
+    The isin strategy is the fastest, the join strategy is kept for comparison.
+    """
+    columns = [rows.columns[column] if isinstance(column, int) else column for column in columns]
+    if strategy == MembershipStrategy.join:
+        for column in columns:
+            allowed: pd.DataFrame = pd.DataFrame({column: pd.Series(list(ids), dtype=rows[column].dtype)})
+            rows = rows.merge(allowed, on=column, how="inner")
+        return rows
+    return rows[rows[columns].isin(ids).all(axis=1)]
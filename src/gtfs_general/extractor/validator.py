@@ -0,0 +1,384 @@
+from __future__ import annotations
+
+import json
+import re
+import tempfile
+from dataclasses import dataclass, field
+from datetime import datetime
+from enum import Enum
+from pathlib import Path
+from typing import Dict, List, Set, Tuple
+
+import pandas as pd
+
+from gtfs_general.extractor.extractor import Extractor
+from gtfs_general.extractor.gtfs import GTFS
+from gtfs_general.extractor.utils import parse_date_from_str
+
+# Colors are six hex digits without a leading #, e.g. FFFFFF
+COLOR_PATTERN: re.Pattern = re.compile(r"^[0-9A-Fa-f]{6}$")
+# Structure of a BCP-47 language tag: language, extlang, script, region, variants, extensions and private use.
+# Only two or three letter languages are accepted, the longer forms of the grammar aren't registered.
+BCP47_PATTERN: re.Pattern = re.compile(
+    r"^(?:[a-z]{2,3}(?:-[a-z]{3}){0,3}(?:-[a-z]{4})?(?:-(?:[a-z]{2}|[0-9]{3}))?"
+    r"(?:-(?:[a-z0-9]{5,8}|[0-9][a-z0-9]{3}))*(?:-[0-9a-wy-z](?:-[a-z0-9]{2,8})+)*(?:-x(?:-[a-z0-9]{1,8})+)?"
+    r"|x(?:-[a-z0-9]{1,8})+)$",
+    re.IGNORECASE,
+)
+
+
+class Severity(str, Enum):
+    error = "error"
+    warning = "warning"
+    # Only used to override the severity of a check, its findings are dropped
+    ignore = "ignore"
+
+
+# The checks of validate with the severity of their findings. Findings that break consumers are errors.
+DEFAULT_SEVERITIES: Dict[str, Severity] = {
+    "line_endings": Severity.warning,
+    "timezones": Severity.warning,
+    "stop_sequences": Severity.error,
+    "fares": Severity.error,
+    "calendar_ranges": Severity.error,
+    "dead_services": Severity.warning,
+    "languages": Severity.error,
+    "colors": Severity.error,
+    "transfers": Severity.error,
+    "coordinates": Severity.warning,
+    "stop_names": Severity.error,
+}
+# All checks whose severity can be overridden, roundtrip is only run on request as it extracts the whole feed
+CHECKS: Tuple[str, ...] = (*DEFAULT_SEVERITIES, "roundtrip")
+
+
+@dataclass
+class Finding:
+    check: str
+    severity: Severity
+    message: str
+
+
+@dataclass
+class ValidationReport:
+    findings: List[Finding] = field(default_factory=list)
+    # Severities by check that replace the default severity the check is reported with
+    severities: Dict[str, Severity] = field(default_factory=dict)
+
+    def extend(self, check: str, severity: Severity, messages: List[str]) -> None:
+        severity = self.severities.get(check, severity)
+        if severity == Severity.ignore:
+            return
+        self.findings.extend(Finding(check=check, severity=severity, message=message) for message in messages)
+
+    def to_dict(self) -> Dict:
+        return {
+            "issues": len(self.findings),
+            "errors": sum(finding.severity == Severity.error for finding in self.findings),
+            "warnings": sum(finding.severity == Severity.warning for finding in self.findings),
+            "findings": [
+                {"check": finding.check, "severity": finding.severity.value, "message": finding.message}
+                for finding in self.findings
+            ],
+        }
+
+    def write_json(self, path: Path) -> None:
+        with open(path, "w") as fp:
+            json.dump(self.to_dict(), fp, indent=2)
+
+
+class Validator(GTFS):
+    def check_timezones(self) -> List[str]:
+        """
+        Report stops with a parent_station that set their own stop_timezone.
+        Child stops inherit the timezone of their parent station.
+        """
+        findings: List[str] = []
+        stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "parent_station", "stop_timezone"])
+        if "parent_station" not in stops.columns or "stop_timezone" not in stops.columns:
+            return findings
+        station_timezones: Dict = stops.dropna(subset=["stop_timezone"]).set_index("stop_id")["stop_timezone"].to_dict()
+        children: pd.DataFrame = stops[stops["parent_station"].notna() & stops["stop_timezone"].notna()]
+        for stop_id, parent_station, stop_timezone in children[["stop_id", "parent_station", "stop_timezone"]].values:
+            parent_timezone: str | None = station_timezones.get(parent_station)
+            if parent_timezone is None:
+                findings.append(
+                    f"stops.txt: stop {stop_id} sets stop_timezone '{stop_timezone}' instead of inheriting it from "
+                    f"its parent station {parent_station}"
+                )
+            elif parent_timezone != stop_timezone:
+                findings.append(
+                    f"stops.txt: stop {stop_id} sets stop_timezone '{stop_timezone}' which conflicts with "
+                    f"'{parent_timezone}' of its parent station {parent_station}"
+                )
+            else:
+                findings.append(
+                    f"stops.txt: stop {stop_id} redundantly sets stop_timezone '{stop_timezone}' of its parent "
+                    f"station {parent_station}"
+                )
+        return findings
+
+    def check_stop_sequences(self) -> List[str]:
+        """
+        Report trips whose stop_sequence does not strictly increase in file order.
+        Each trip is reported once, at its first repeated or decreasing value.
+        """
+        findings: List[str] = []
+        stop_times: pd.DataFrame = self.read_table("stop_times.txt", usecols=["trip_id", "stop_sequence"])
+        previous: pd.Series = stop_times.groupby("trip_id", sort=False)["stop_sequence"].shift()
+        violations: pd.DataFrame = stop_times.assign(previous=previous)[
+            (stop_times["stop_sequence"] <= previous).fillna(False).astype(bool)
+        ].drop_duplicates("trip_id")
+        for trip_id, stop_sequence, previous_sequence in violations[["trip_id", "stop_sequence", "previous"]].values:
+            if stop_sequence == previous_sequence:
+                findings.append(f"stop_times.txt: trip {trip_id} repeats stop_sequence {stop_sequence}")
+            else:
+                findings.append(
+                    f"stop_times.txt: trip {trip_id} has stop_sequence {stop_sequence} after {previous_sequence}"
+                )
+        return findings
+
+    def check_fares(self) -> List[str]:
+        """
+        Report fare_rules.txt references to fares, routes and zones that don't exist.
+        Feeds without fare_rules.txt are skipped.
+        """
+        findings: List[str] = []
+        if not self._gtfs_files.fare_rules.exists():
+            return findings
+        fare_rules: pd.DataFrame = self.read_table("fare_rules.txt")
+        fare_ids: Set[str] = set()
+        if self._gtfs_files.fare_attributes.exists():
+            fare_ids = set(self.read_table("fare_attributes.txt", usecols=["fare_id"])["fare_id"].dropna())
+        route_ids: Set[str] = set(self.read_table("routes.txt", usecols=["route_id"])["route_id"].dropna())
+        stops: pd.DataFrame = self.read_table("stops.txt", usecols=["zone_id"])
+        zone_ids: Set[str] = set(stops["zone_id"].dropna()) if "zone_id" in stops.columns else set()
+        column: str
+        known_ids: Set[str]
+        source: str
+        for column, known_ids, source in (
+            ("fare_id", fare_ids, "fare_attributes.txt"),
+            ("route_id", route_ids, "routes.txt"),
+            ("origin_id", zone_ids, "the zone_id of stops.txt"),
+            ("destination_id", zone_ids, "the zone_id of stops.txt"),
+            ("contains_id", zone_ids, "the zone_id of stops.txt"),
+        ):
+            if column not in fare_rules.columns:
+                continue
+            dangling_id: str
+            for dangling_id in sorted(set(fare_rules[column].dropna()) - known_ids):
+                findings.append(f"fare_rules.txt: {column} {dangling_id} is missing from {source}")
+        return findings
+
+    def check_calendar_ranges(self) -> List[str]:
+        """
+        Report calendar.txt rows whose start_date lies after their end_date, they define no service at all.
+        """
+        findings: List[str] = []
+        calendar: pd.DataFrame = self.read_table("calendar.txt", usecols=["service_id", "start_date", "end_date"])
+        service_id: str
+        start_date: str
+        end_date: str
+        for service_id, start_date, end_date in calendar[["service_id", "start_date", "end_date"]].dropna().values:
+            if parse_date_from_str(start_date) > parse_date_from_str(end_date):
+                findings.append(
+                    f"calendar.txt: service_id {service_id} has start_date {start_date} after end_date {end_date}"
+                )
+        return findings
+
+    def check_dead_services(self) -> List[str]:
+        """
+        Report the service_ids of trips that are never active, e.g. all weekdays are 0 and no dates are added.
+        """
+        findings: List[str] = []
+        service_dates: Dict = self._service_dates()
+        trips: pd.DataFrame = self.read_table("trips.txt", usecols=["trip_id", "service_id"])
+        dead: pd.DataFrame = trips[~trips["service_id"].map(lambda service_id: bool(service_dates.get(service_id)))]
+        service_id: str
+        service_trips: pd.DataFrame
+        for service_id, service_trips in dead.groupby("service_id", sort=True):
+            findings.append(
+                f"trips.txt: {len(service_trips)} trips use service_id {service_id} which is never active, "
+                f"e.g. trip {service_trips['trip_id'].iloc[0]}"
+            )
+        return findings
+
+    def check_languages(self) -> List[str]:
+        """
+        Report language fields that aren't well-formed BCP-47 tags, e.g. "de" or "en-US".
+        Empty fields are valid, the fields are optional.
+        """
+        findings: List[str] = []
+        file_name: str
+        column: str
+        for file_name, column in (
+            ("agency.txt", "agency_lang"),
+            ("feed_info.txt", "feed_lang"),
+            ("feed_info.txt", "default_lang"),
+            ("translations.txt", "language"),
+        ):
+            if not getattr(self._gtfs_files, Path(file_name).stem).exists():
+                continue
+            languages: pd.DataFrame = self.read_table(file_name, usecols=[column])
+            if column not in languages.columns:
+                continue
+            row: int
+            language: str
+            for row, language in languages[column].dropna().items():
+                if not BCP47_PATTERN.match(language):
+                    findings.append(
+                        f"{file_name}: row {row + 1} has {column} '{language}' which isn't a well-formed BCP-47 tag"
+                    )
+        return findings
+
+    def check_colors(self) -> List[str]:
+        """
+        Report color fields of routes.txt, stops.txt and shapes.txt that aren't six hex digits, e.g. "#FF0000".
+        Every column ending in _color is checked, empty fields are valid.
+        """
+        findings: List[str] = []
+        file_name: str
+        for file_name in ("routes.txt", "stops.txt", "shapes.txt"):
+            file_path: Path = getattr(self._gtfs_files, Path(file_name).stem)
+            if not file_path.exists():
+                continue
+            header: pd.Index = pd.read_csv(file_path, nrows=0).columns
+            color_columns: List[str] = [column for column in header if column.endswith("_color")]
+            if not color_columns:
+                continue
+            # Read as text, the non-standard columns would be inferred, e.g. 000000 as the number 0
+            colors: pd.DataFrame = pd.read_csv(file_path, dtype=str, usecols=color_columns, na_values=self._null_values)
+            column: str
+            for column in color_columns:
+                row: int
+                color: str
+                for row, color in colors[column].dropna().items():
+                    if not COLOR_PATTERN.match(color):
+                        findings.append(f"{file_name}: row {row + 1} has {column} '{color}' which isn't a hex color")
+        return findings
+
+    def check_transfers(self) -> List[str]:
+        """
+        Report transfers.txt rows with a transfer_type outside 0 to 3, rows of transfer_type 2 without a
+        min_transfer_time and rows of other types that set one. An empty transfer_type means 0.
+        Feeds without transfers.txt are skipped.
+        """
+        findings: List[str] = []
+        if not self._gtfs_files.transfers.exists():
+            return findings
+        transfers: pd.DataFrame = self.read_table("transfers.txt", usecols=["transfer_type", "min_transfer_time"])
+        transfer_types: pd.Series = (
+            transfers["transfer_type"].fillna(0)
+            if "transfer_type" in transfers.columns
+            else pd.Series(0, index=transfers.index)
+        )
+        min_transfer_times: pd.Series = (
+            transfers["min_transfer_time"]
+            if "min_transfer_time" in transfers.columns
+            else pd.Series(pd.NA, index=transfers.index, dtype="Int64")
+        )
+        row: int
+        transfer_type: int
+        for row, transfer_type in transfer_types.items():
+            min_transfer_time: int | None = min_transfer_times[row]
+            if not 0 <= transfer_type <= 3:
+                findings.append(f"transfers.txt: row {row + 1} has transfer_type {transfer_type} which isn't 0 to 3")
+            elif transfer_type == 2 and pd.isna(min_transfer_time):
+                findings.append(f"transfers.txt: row {row + 1} has transfer_type 2 without a min_transfer_time")
+            elif transfer_type != 2 and not pd.isna(min_transfer_time):
+                findings.append(
+                    f"transfers.txt: row {row + 1} sets min_transfer_time {min_transfer_time} although its "
+                    f"transfer_type is {transfer_type} instead of 2"
+                )
+        return findings
+
+    def check_coordinates(self) -> List[str]:
+        """
+        Report stops whose stop_lat or stop_lon lie outside the WGS84 range. Many of them hint at projected
+        coordinates, e.g. UTM meters, which bbox and distance extractions can't handle.
+        """
+        findings: List[str] = []
+        stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "stop_lat", "stop_lon"])
+        outside: pd.DataFrame = stops[(stops["stop_lat"].abs() > 90) | (stops["stop_lon"].abs() > 180)]
+        if outside.empty:
+            return findings
+        stop_id: str
+        stop_lat: float
+        stop_lon: float
+        stop_id, stop_lat, stop_lon = outside[["stop_id", "stop_lat", "stop_lon"]].iloc[0]
+        findings.append(
+            f"stops.txt: {len(outside)} of {len(stops)} stops have coordinates outside the WGS84 range, e.g. stop "
+            f"{stop_id} at {stop_lat}, {stop_lon}. The coordinates may be projected, e.g. in UTM meters"
+        )
+        return findings
+
+    def check_stop_names(self) -> List[str]:
+        """
+        Report stops, stations and entrances without a stop_name. An empty location_type means 0.
+        Generic nodes (location_type 3) and boarding areas (location_type 4) may omit it.
+        """
+        findings: List[str] = []
+        stops: pd.DataFrame = self.read_table("stops.txt", usecols=["stop_id", "stop_name", "location_type"])
+        location_types: pd.Series = (
+            stops["location_type"].fillna(0) if "location_type" in stops.columns else pd.Series(0, index=stops.index)
+        )
+        stop_names: pd.Series = (
+            stops["stop_name"].str.strip() if "stop_name" in stops.columns else pd.Series(pd.NA, index=stops.index)
+        )
+        unnamed: pd.Series = location_types.isin([0, 1, 2]) & (stop_names.isna() | (stop_names == ""))
+        stop_id: str
+        location_type: int
+        for stop_id, location_type in zip(stops.loc[unnamed, "stop_id"], location_types[unnamed]):
+            findings.append(f"stops.txt: stop {stop_id} of location_type {location_type} has no stop_name")
+        return findings
+
+    def _full_service_range(self) -> List[datetime]:
+        calendar: pd.DataFrame = self.read_table("calendar.txt", usecols=["start_date", "end_date"])
+        calendar_dates: pd.DataFrame = self.read_table("calendar_dates.txt", usecols=["date"])
+        start_dates: List[str] = calendar["start_date"].dropna().tolist() + calendar_dates["date"].dropna().tolist()
+        end_dates: List[str] = calendar["end_date"].dropna().tolist() + calendar_dates["date"].dropna().tolist()
+        return [parse_date_from_str(min(start_dates)), parse_date_from_str(max(end_dates))]
+
+    def _count_rows(self, file_name: str) -> int:
+        return sum(len(batch) for batch in self.table_batches(file_name, 100000))
+
+    def check_roundtrip(self) -> List[str]:
+        """
+        Extract the whole service range of the feed and report the tables whose row count changed.
+        All services lie within that range, so calendar, calendar_dates, trips and stop_times must be kept completely.
+        """
+        findings: List[str] = []
+        start_date, end_date = self._full_service_range()
+        with tempfile.TemporaryDirectory(dir=self._temp_dir) as output_folder:
+            extractor: Extractor = Extractor(
+                input_object=self._feed_folder,
+                output_folder=Path(output_folder),
+                scheduler=self._scheduler,
+                cpu_count=self._cpu_count,
+                null_values=self._null_values,
+                fail_on_warning=self._fail_on_warning,
+            )
+            extractor.extract_by_date(start_date, end_date)
+            extractor.close()
+            with GTFS(input_object=Path(output_folder), null_values=self._null_values) as output:
+                file_name: str
+                for file_name in ("calendar.txt", "calendar_dates.txt", "trips.txt", "stop_times.txt"):
+                    input_rows: int = self._count_rows(file_name)
+                    output_rows: int = sum(len(batch) for batch in output.table_batches(file_name, 100000))
+                    if input_rows != output_rows:
+                        findings.append(
+                            f"{file_name}: extracting the whole service range kept {output_rows} of {input_rows} rows"
+                        )
+        return findings
+
+    def validate(self, severities: Dict[str, Severity] | None = None) -> ValidationReport:
+        """
+        Run all checks and collect their findings in one report.
+        Findings that break consumers are errors, the others are warnings. severities overrides them by check name.
+        """
+        report: ValidationReport = ValidationReport(severities=severities or {})
+        check: str
+        severity: Severity
+        for check, severity in DEFAULT_SEVERITIES.items():
+            report.extend(check, severity, getattr(self, f"check_{check}")())
+        return report
@@ -1,9 +1,17 @@
+import json
 import logging
 import sys
+from datetime import datetime, timezone
+from enum import Enum
 
 from gtfs_general import logger
 
 
+class LogFormat(str, Enum):
+    text = "text"
+    json = "json"
+
+
 class CustomFormatter(logging.Formatter):
     grey = "\x1b[38;20m"
     yellow = "\x1b[33;20m"
@@ -27,17 +35,32 @@ class CustomFormatter(logging.Formatter):
         return formatter.format(record)
 
 
-def initialize_logging(level: str = "info") -> None:
+class JsonFormatter(logging.Formatter):
+    def format(self, record: logging.LogRecord) -> str:
+        return json.dumps(
+            {
+                "timestamp": datetime.fromtimestamp(record.created, tz=timezone.utc).isoformat(),
+                "level": record.levelname,
+                "name": record.name,
+                "message": record.getMessage(),
+            }
+        )
+
+
+def initialize_logging(level: str = "info", log_format: LogFormat = LogFormat.text) -> None:
     correct_level = logging.getLevelName(level)
     logger.setLevel(correct_level)
+    # Replace the handlers of earlier initializations instead of logging every line twice
+    logger.handlers.clear()
 
-    stdout_handler = logging.StreamHandler(sys.stdout)
-    stdout_handler.setLevel(correct_level)
-    stdout_handler.setFormatter(CustomFormatter())
+    # The logs go to stderr, so stdout only carries the output of a command, e.g. its JSON
+    stderr_handler = logging.StreamHandler(sys.stderr)
+    stderr_handler.setLevel(correct_level)
+    stderr_handler.setFormatter(JsonFormatter() if log_format == LogFormat.json else CustomFormatter())
 
     # file_handler = logging.FileHandler("logs.log")
     # file_handler.setLevel(correct_level)
     # file_handler.setFormatter(CustomFormatter())
 
     # logger.addHandler(file_handler)
-    logger.addHandler(stdout_handler)
+    logger.addHandler(stderr_handler)
@@ -1,25 +1,44 @@
 from __future__ import annotations
 
+import json
 import os
+import sys
 import time
-from dataclasses import dataclass
-from datetime import datetime
-from functools import partialmethod
+from dataclasses import dataclass, fields
+from datetime import date, datetime
+from enum import Enum
+from functools import partialmethod, wraps
+from inspect import Parameter, signature
 from pathlib import Path
-from typing import List, Optional
+from typing import Any, Callable, Dict, List, Optional, Tuple, cast, get_type_hints
 
+import click
+import pandas as pd
 import typer
 import uvicorn
+from pandas.api.types import is_numeric_dtype
+from rich.console import Console
+from rich.table import Table
 from tqdm import tqdm
 
-from . import __app_name__, __version__, logger
+from . import EMPTY_RESULT_ERROR, OVERWRITE_ERROR, VALIDATION_ERROR, __app_name__, __version__, logger
 from .application import StandaloneApplication, create_app
 from .dask_config import initialize_dask
 from .docs import app as docs_app
 from .extractor.bbox import Bbox
-from .extractor.extractor import Extractor
-from .extractor.gtfs import GTFS
-from .logging import initialize_logging
+from .extractor.extractor import (
+    CalendarForm,
+    Extractor,
+    HeadsignStrategy,
+    MissingShapePolicy,
+    OutputEncoding,
+    QuoteStyle,
+)
+from .extractor.gtfs import GTFS, WEEKDAYS, GtfsDtypes, GtfsFiles
+from .extractor.polygon import Polygon
+from .extractor.utils import MembershipStrategy, format_bytes
+from .extractor.validator import CHECKS, Finding, Severity, ValidationReport, Validator
+from .logging import LogFormat, initialize_logging
 
 app = typer.Typer()
 
@@ -40,14 +59,282 @@ def _version_callback(value: bool) -> None:
         raise typer.Exit()
 
 
+def _check_date(value: Optional[str]) -> Optional[str]:
+    if value is None:
+        return value
+    try:
+        datetime.strptime(value, "%Y%m%d")
+    except ValueError:
+        raise typer.BadParameter(f"{value} isn't a date in the format YYYYMMDD, e.g. 20221002")
+    return value
+
+
+def _stdout_is_terminal() -> bool:
+    return sys.stdout.isatty()
+
+
+def _echo_table(ctx: typer.Context, table: pd.DataFrame) -> None:
+    if not ctx.obj.pretty or not _stdout_is_terminal():
+        typer.echo(table.to_string(index=False))
+        return
+    pretty_table: Table = Table()
+    column: str
+    for column in table.columns:
+        pretty_table.add_column(str(column), justify="right" if is_numeric_dtype(table[column]) else "left")
+    row: Tuple
+    for row in table.itertuples(index=False):
+        pretty_table.add_row(*["" if pd.isna(value) else str(value) for value in row])
+    Console().print(pretty_table)
+
+
+def _feed_publisher(
+    synthesize_feed_info: bool, feed_publisher_name: str | None, feed_publisher_url: str | None
+) -> Tuple[str, str] | None:
+    if not synthesize_feed_info:
+        return None
+    if not feed_publisher_name or not feed_publisher_url:
+        raise typer.BadParameter("--synthesize-feed-info needs --feed-publisher-name and --feed-publisher-url")
+    return feed_publisher_name, feed_publisher_url
+
+
+def _projections(project: List[str] | None) -> Dict[str, List[str]] | None:
+    if not project:
+        return None
+    projections: Dict[str, List[str]] = {}
+    projection: str
+    for projection in project:
+        file_name, _, columns = projection.partition(":")
+        if not file_name or not columns:
+            raise typer.BadParameter(f"--project expects FILE:COLUMN,COLUMN,... but got {projection}")
+        projections[file_name.strip()] = [column.strip() for column in columns.split(",") if column.strip()]
+    return projections
+
+
+def _severities(severity: List[str] | None) -> Dict[str, Severity]:
+    severities: Dict[str, Severity] = {}
+    override: str
+    for override in severity or []:
+        check, _, value = (part.strip() for part in override.partition("="))
+        # warn is accepted as the short form of warning
+        value = Severity.warning.value if value == "warn" else value
+        if not check or value not in {member.value for member in Severity}:
+            raise typer.BadParameter(
+                f"--severity expects CHECK=error|warning|ignore but got {override}", param_hint="--severity"
+            )
+        if check not in CHECKS:
+            raise typer.BadParameter(
+                f"Unknown check {check}. The checks are {', '.join(sorted(CHECKS))}", param_hint="--severity"
+            )
+        severities[check] = Severity(value)
+    return severities
+
+
+def _confirm_overwrite(ctx: typer.Context, output_folder: str, output_prefix: str = "", suffix: str = ".txt") -> None:
+    """
+    Ask before overwriting GTFS files in the output folder. Without a terminal to ask on, --yes is required.
+    Only the files the command writes count, other files like a README.txt are left alone anyway.
+    """
+    file_names: List[str] = [
+        f"{output_prefix}{Path(file_name).stem}{suffix}"
+        for file_name in GtfsFiles.required_files + GtfsFiles.optional_files
+    ]
+    existing_files: List[str] = sorted(
+        file_name for file_name in file_names if Path(output_folder).joinpath(file_name).exists()
+    )
+    if not existing_files or ctx.obj.yes:
+        return
+    if not sys.stdin.isatty():
+        logger.error(
+            f"{output_folder} already contains {len(existing_files)} files that would be overwritten. "
+            "Pass --yes to overwrite them."
+        )
+        raise typer.Exit(code=OVERWRITE_ERROR)
+    typer.confirm(f"Overwrite {', '.join(existing_files)} in {output_folder}?", abort=True)
+
+
+class OutputFormat(str, Enum):
+    text = "text"
+    json = "json"
+
+
 @dataclass
 class Shared:
     cpu_count: int
+    max_output_rows: Optional[int] = None
+    null_values: Optional[List[str]] = None
+    temp_dir: Optional[Path] = None
+    yes: bool = False
+    fail_on_warning: bool = False
+    io_retries: int = 0
+    pretty: bool = False
+
+
+@dataclass
+class OutputOptions:
+    """The options that all extract commands share. _with_output_options adds them to the command."""
+
+    quote_style: QuoteStyle = typer.Option(
+        QuoteStyle.always,
+        help="Quoting of the output CSV fields. Fields that can't be written unquoted are always quoted.",
+    )
+    include: Optional[List[str]] = typer.Option(
+        None,
+        help="Only write the given GTFS file to the output. Can be repeated. Required files are always written.",
+    )
+    crlf: bool = typer.Option(False, help="Write Windows line endings (\\r\\n) instead of \\n.")
+    output_encoding: OutputEncoding = typer.Option(
+        OutputEncoding.utf8, help="Encoding of the written files, e.g. latin1 for legacy consumers."
+    )
+    lossy: bool = typer.Option(
+        False, help="Write characters that the output encoding can't represent as ? instead of failing."
+    )
+    preserve_extra_files: bool = typer.Option(
+        False, help="Copy files that aren't GTFS tables, e.g. a LICENSE, unchanged to the output."
+    )
+    dedupe_stop_times: bool = typer.Option(
+        False, help="Drop stop_times.txt rows that repeat the trip_id and stop_sequence of an earlier row."
+    )
+    sort_output: bool = typer.Option(
+        False,
+        help="Sort routes.txt by route_id, trips.txt by route_id, service_id and trip_id and stops.txt by stop_id. "
+        "Numeric ids are sorted by value.",
+    )
+    project: Optional[List[str]] = typer.Option(
+        None,
+        help='Only write the given columns of a file, e.g. "stop_times.txt:trip_id,stop_id,stop_sequence". All rows '
+        "are kept. Can be repeated. The result may not be a valid feed.",
+    )
+    on_missing_shape: MissingShapePolicy = typer.Option(
+        MissingShapePolicy.keep,
+        help="Handle trips whose shape_id is missing from shapes.txt. keep: Leave them unchanged. clear: Blank their "
+        "shape_id. drop-trip: Remove the trips.",
+    )
+    max_routes: Optional[int] = typer.Option(
+        None,
+        min=1,
+        help="Only keep the trips of the first N routes by route_id that the extraction would keep, e.g. for small "
+        "demo feeds.",
+    )
+    exclude_route_type: Optional[List[int]] = typer.Option(
+        None, help="Drop the routes of this route_type, e.g. 2 for rail, and their trips. Can be repeated."
+    )
+    only_route_type: Optional[List[int]] = typer.Option(
+        None, help="Only keep the routes of this route_type, e.g. 3 for bus, and their trips. Can be repeated."
+    )
+    synthesize_feed_info: bool = typer.Option(
+        False,
+        help="Write a minimal feed_info.txt if the feed has none. Needs --feed-publisher-name and "
+        "--feed-publisher-url.",
+    )
+    feed_publisher_name: Optional[str] = typer.Option(None, help="feed_publisher_name of a synthesized feed_info.txt")
+    feed_publisher_url: Optional[str] = typer.Option(None, help="feed_publisher_url of a synthesized feed_info.txt")
+    output_prefix: str = typer.Option(
+        "",
+        help="Prefix the names of the written files, e.g. 2022w40_ for 2022w40_stops.txt. Prefixed files aren't "
+        "recognized as GTFS tables until they are renamed.",
+    )
+    membership_strategy: MembershipStrategy = typer.Option(
+        MembershipStrategy.isin, hidden=True, help="Strategy used to filter rows by ids. Meant for comparisons."
+    )
+
+    def extractor(self, shared: Shared, input_object: str, output_folder: str, **kwargs: Any) -> Extractor:
+        return Extractor(
+            input_object=Path(input_object),
+            output_folder=Path(output_folder),
+            cpu_count=shared.cpu_count,
+            max_output_rows=shared.max_output_rows,
+            null_values=shared.null_values,
+            temp_dir=shared.temp_dir,
+            fail_on_warning=shared.fail_on_warning,
+            io_retries=shared.io_retries,
+            quote_style=self.quote_style,
+            include_files=self.include,
+            line_terminator="\r\n" if self.crlf else "\n",
+            output_encoding=self.output_encoding,
+            lossy_encoding=self.lossy,
+            preserve_extra_files=self.preserve_extra_files,
+            dedupe_stop_times=self.dedupe_stop_times,
+            sort_output=self.sort_output,
+            projections=_projections(self.project),
+            on_missing_shape=self.on_missing_shape,
+            max_routes=self.max_routes,
+            exclude_route_types=self.exclude_route_type,
+            only_route_types=self.only_route_type,
+            feed_publisher=_feed_publisher(
+                self.synthesize_feed_info, self.feed_publisher_name, self.feed_publisher_url
+            ),
+            output_prefix=self.output_prefix,
+            membership_strategy=self.membership_strategy,
+            **kwargs,
+        )
+
+
+def _with_output_options(command: Callable[..., None]) -> Callable[..., None]:
+    """
+    Replace the output_options parameter of an extract command with one option per OutputOptions field. typer reads
+    the options from the signature and the resolved annotations and passes them back as keyword arguments.
+    """
+    option_hints: Dict[str, Any] = get_type_hints(OutputOptions)
+    command_hints: Dict[str, Any] = get_type_hints(command)
+    option_names: List[str] = [field.name for field in fields(OutputOptions)]
+
+    @wraps(command)
+    def wrapper(*args: Any, **kwargs: Any) -> None:
+        options: OutputOptions = OutputOptions(**{name: kwargs.pop(name) for name in option_names})
+        command(*args, output_options=options, **kwargs)
+
+    parameters: List[Parameter] = [
+        parameter for parameter in signature(command).parameters.values() if parameter.name != "output_options"
+    ]
+    parameters += [
+        Parameter(field.name, Parameter.KEYWORD_ONLY, default=field.default, annotation=option_hints[field.name])
+        for field in fields(OutputOptions)
+    ]
+    wrapper.__signature__ = signature(command).replace(parameters=parameters)  # type: ignore[attr-defined]
+    wrapper.__annotations__ = {
+        **{name: hint for name, hint in command_hints.items() if name != "output_options"},
+        **option_hints,
+    }
+    return wrapper
+
+
+@app.command(help="Extract the GTFS data of the given agencies.")
+@_with_output_options
+def extract_agencies(
+    ctx: typer.Context,
+    output_options: OutputOptions,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_folder: str = typer.Option(..., help="Directory to which the GFTS files are written"),
+    agency_id: List[str] = typer.Option(..., help="The agency_id of an agency to keep. Can be repeated."),
+) -> None:
+    logger.info("#################################")
+    logger.info("###### Extract by agencies ######")
+    logger.info(f"Input: {input_object}")
+    logger.info(f"Agencies: {agency_id}")
+    logger.info("#################################")
+    logger.info("####### Start processing ########")
+    _confirm_overwrite(ctx, output_folder, output_options.output_prefix)
+    extractor: Extractor = output_options.extractor(ctx.obj, input_object, output_folder)
+    files: List = extractor.extract_by_agency(agencies=agency_id)
+    extractor.close()
+    logger.info("#################################")
+    logger.info("############ Result ############")
+    run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
+    logger.info(f"Run time: {run_time}")
+    logger.info(f"Processed {len(files)} files:")
+    file: Path
+    for file in files:
+        logger.info(file.__str__())
+    logger.info("################################")
+    if extractor.trip_count == 0:
+        raise typer.Exit(code=EMPTY_RESULT_ERROR)
 
 
-@app.command()
+@app.command(help="Extract the GTFS data inside a bounding box.")
+@_with_output_options
 def extract_bbox(
     ctx: typer.Context,
+    output_options: OutputOptions,
     input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
     output_folder: str = typer.Option(..., help="Directory to which the GFTS files are written"),
     bbox: str = typer.Option(
@@ -55,6 +342,11 @@ def extract_bbox(
         help="The bbox for selecting the GTFS data to keep. Format is WGS84 Coordinates lon/lat (lon min, lat min, "
         'lon max, lat max) Example: "8.573179,49.352003,8.79405,49.459693"',
     ),
+    clip_shapes: bool = typer.Option(
+        False,
+        help="Trim the kept shapes to their points inside the bbox and one point on each side, so the lines reach "
+        "the boundary.",
+    ),
 ) -> None:
     coordinates: List[float] = [float(x.strip()) for x in bbox.split(",")]
     logger.info("#################################")
@@ -64,12 +356,9 @@ def extract_bbox(
     logger.info("#################################")
     logger.info("####### Start processing ########")
     keep_bbox: Bbox = Bbox(*coordinates)
-    extractor: Extractor = Extractor(
-        input_object=Path(input_object),
-        output_folder=Path(output_folder),
-        cpu_count=ctx.obj.cpu_count,
-    )
-    files: List = extractor.extract_by_bbox(bbox=keep_bbox)
+    _confirm_overwrite(ctx, output_folder, output_options.output_prefix)
+    extractor: Extractor = output_options.extractor(ctx.obj, input_object, output_folder)
+    files: List = extractor.extract_by_bbox(bbox=keep_bbox, clip_shapes=clip_shapes)
     extractor.close()
     logger.info("#################################")
     logger.info("############ Result ############")
@@ -80,40 +369,270 @@ def extract_bbox(
     for file in files:
         logger.info(file.__str__())
     logger.info("################################")
+    if extractor.trip_count == 0:
+        raise typer.Exit(code=EMPTY_RESULT_ERROR)
 
 
-@app.command()
+@app.command(help="Extract the GTFS data of the services running within a date range.")
+@_with_output_options
 def extract_date(
     ctx: typer.Context,
+    output_options: OutputOptions,
     input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
     output_folder: str = typer.Option(..., help="Directory to which the GFTS files are written"),
     start_date: str = typer.Option(
         ...,
+        callback=_check_date,
         help="Lower date boundary. Format: YYYYMMDD. e.g. 20221002 for 2nd October 2022",
     ),
     end_date: str = typer.Option(
         ...,
-        help="Lower date boundary. Format: YYYYMMDD. e.g. 20221002 for 2nd October 2022",
+        callback=_check_date,
+        help="Upper date boundary. Inclusive unless --end-exclusive is set. Format: YYYYMMDD. e.g. 20221002 for 2nd "
+        "October 2022",
+    ),
+    end_exclusive: bool = typer.Option(
+        False,
+        help="Treat the end date as exclusive. By default services running on the end date are kept.",
+    ),
+    clamp_to_feed_info: bool = typer.Option(
+        False,
+        help="Intersect the date range with the feed_start_date and feed_end_date of feed_info.txt. Fails if they "
+        "don't overlap.",
+    ),
+    trim_calendar: bool = typer.Option(
+        False,
+        help="Clamp the start_date and end_date of the kept calendar.txt rows to the date range.",
+    ),
+    append: bool = typer.Option(
+        False,
+        help="Merge the extracted rows into the feed in the output folder instead of overwriting its files. Rows "
+        "with the id of an existing row replace it, an appended trip or shape replaces all its stop_times, frequencies "
+        "or points. The calendar.txt rows of a service and feed_info.txt are widened to cover both date ranges.",
     ),
 ) -> None:
     logger.info("#################################")
     logger.info("######## Extract by date ########")
     logger.info(f"Input: {input_object}")
     logger.info(f"Start date: {start_date}")
-    logger.info(f"End date: {end_date}")
+    logger.info(f"End date: {end_date}{' (exclusive)' if end_exclusive else ''}")
     logger.info("#################################")
     logger.info("####### Start processing ########")
+    if not append:
+        _confirm_overwrite(ctx, output_folder, output_options.output_prefix)
+    extractor: Extractor = output_options.extractor(ctx.obj, input_object, output_folder, append=append)
+    files: List = extractor.extract_by_date(
+        start_date=datetime.strptime(start_date, "%Y%m%d"),
+        end_date=datetime.strptime(end_date, "%Y%m%d"),
+        end_exclusive=end_exclusive,
+        clamp_to_feed_info=clamp_to_feed_info,
+        trim_calendar=trim_calendar,
+    )
+    extractor.close()
+    logger.info("################################")
+    logger.info("############ Result ############")
+    run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
+    logger.info(f"Run time: {run_time}")
+    logger.info(f"Processed {len(files)} files:")
+    file: Path
+    for file in files:
+        logger.info(file.__str__())
+    logger.info("################################")
+    if extractor.trip_count == 0:
+        raise typer.Exit(code=EMPTY_RESULT_ERROR)
+
+
+@app.command(help="Extract the stop_times within a distance band along the shapes of their trips.")
+@_with_output_options
+def extract_distance(
+    ctx: typer.Context,
+    output_options: OutputOptions,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_folder: str = typer.Option(..., help="Directory to which the GFTS files are written"),
+    min_distance: float = typer.Option(..., help="Keep stop_times with a shape_dist_traveled of at least this value."),
+    max_distance: float = typer.Option(..., help="Keep stop_times with a shape_dist_traveled of at most this value."),
+) -> None:
+    logger.info("#################################")
+    logger.info("###### Extract by distance ######")
+    logger.info(f"Input: {input_object}")
+    logger.info(f"Distance band: {min_distance} to {max_distance}")
+    logger.info("#################################")
+    logger.info("####### Start processing ########")
+    _confirm_overwrite(ctx, output_folder, output_options.output_prefix)
+    extractor: Extractor = output_options.extractor(ctx.obj, input_object, output_folder)
+    files: List = extractor.extract_by_distance(min_distance=min_distance, max_distance=max_distance)
+    extractor.close()
+    logger.info("#################################")
+    logger.info("############ Result ############")
+    run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
+    logger.info(f"Run time: {run_time}")
+    logger.info(f"Processed {len(files)} files:")
+    file: Path
+    for file in files:
+        logger.info(file.__str__())
+    logger.info("################################")
+    if extractor.trip_count == 0:
+        raise typer.Exit(code=EMPTY_RESULT_ERROR)
+
+
+@app.command(help="Extract a random sample of the trips of each route.")
+def extract_sample(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_folder: str = typer.Option(..., help="Directory to which the GFTS files are written"),
+    trips_per_route: int = typer.Option(..., min=1, help="The number of trips to keep per route."),
+    seed: int = typer.Option(
+        0,
+        min=0,
+        max=2**32 - 1,
+        help="Seed of the random sample. The same seed draws the same trips, so runs are reproducible by default.",
+    ),
+) -> None:
+    logger.info("################################")
+    logger.info("######## Extract sample ########")
+    logger.info(f"Input: {input_object}")
+    logger.info(f"Trips per route: {trips_per_route}")
+    logger.info(f"Seed: {seed}")
+    logger.info("################################")
+    logger.info("####### Start processing #######")
+    _confirm_overwrite(ctx, output_folder)
     extractor: Extractor = Extractor(
         input_object=Path(input_object),
         output_folder=Path(output_folder),
         cpu_count=ctx.obj.cpu_count,
+        max_output_rows=ctx.obj.max_output_rows,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
     )
-    files: List = extractor.extract_by_date(
-        start_date=datetime.strptime(start_date, "%Y%m%d"),
-        end_date=datetime.strptime(end_date, "%Y%m%d"),
+    files: List = extractor.extract_sample(trips_per_route=trips_per_route, seed=seed)
+    extractor.close()
+    logger.info("############ Result ############")
+    run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
+    logger.info(f"Run time: {run_time}")
+    logger.info(f"Processed {len(files)} files:")
+    file: Path
+    for file in files:
+        logger.info(file.__str__())
+    logger.info("################################")
+    if extractor.trip_count == 0:
+        raise typer.Exit(code=EMPTY_RESULT_ERROR)
+
+
+@app.command(help="Extract the GTFS data inside a GeoJSON polygon.")
+def extract_polygon(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_folder: str = typer.Option(..., help="Directory to which the GFTS files are written"),
+    geojson: Path = typer.Option(
+        ...,
+        exists=True,
+        dir_okay=False,
+        help="GeoJSON file with the Polygon or MultiPolygon geometries of the area to keep. Coordinates are WGS84 "
+        "lon/lat.",
+    ),
+) -> None:
+    logger.info("################################")
+    logger.info("###### Extract by polygon ######")
+    logger.info(f"Input: {input_object}")
+    logger.info(f"Polygon: {geojson}")
+    logger.info("################################")
+    logger.info("####### Start processing #######")
+    polygon: Polygon = Polygon.from_geojson(geojson)
+    _confirm_overwrite(ctx, output_folder)
+    extractor: Extractor = Extractor(
+        input_object=Path(input_object),
+        output_folder=Path(output_folder),
+        cpu_count=ctx.obj.cpu_count,
+        max_output_rows=ctx.obj.max_output_rows,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
     )
+    files: List = extractor.extract_by_polygon(polygon=polygon)
     extractor.close()
+    logger.info("############ Result ############")
+    run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
+    logger.info(f"Run time: {run_time}")
+    logger.info(f"Processed {len(files)} files:")
+    file: Path
+    for file in files:
+        logger.info(file.__str__())
     logger.info("################################")
+    if extractor.trip_count == 0:
+        raise typer.Exit(code=EMPTY_RESULT_ERROR)
+
+
+@app.command(help="Extract the given trips and the GTFS data they use.")
+def extract_trips(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_folder: str = typer.Option(..., help="Directory to which the GFTS files are written"),
+    trip_id: List[str] = typer.Option(..., help="The trip_id of a trip to keep. Can be repeated."),
+) -> None:
+    logger.info("################################")
+    logger.info("######## Extract by trips ######")
+    logger.info(f"Input: {input_object}")
+    logger.info(f"Trips: {trip_id}")
+    logger.info("################################")
+    logger.info("####### Start processing #######")
+    _confirm_overwrite(ctx, output_folder)
+    extractor: Extractor = Extractor(
+        input_object=Path(input_object),
+        output_folder=Path(output_folder),
+        cpu_count=ctx.obj.cpu_count,
+        max_output_rows=ctx.obj.max_output_rows,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    files: List = extractor.extract_by_trip_ids(trip_ids=trip_id)
+    extractor.close()
+    logger.info("############ Result ############")
+    run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
+    logger.info(f"Run time: {run_time}")
+    logger.info(f"Processed {len(files)} files:")
+    file: Path
+    for file in files:
+        logger.info(file.__str__())
+    logger.info("################################")
+    if extractor.trip_count == 0:
+        raise typer.Exit(code=EMPTY_RESULT_ERROR)
+
+
+@app.command(help="Convert the service definitions between calendar.txt and calendar_dates.txt.")
+def convert_calendar(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_folder: str = typer.Option(..., help="Directory to which the GFTS files are written"),
+    to: CalendarForm = typer.Option(
+        ...,
+        help="dates: Expand calendar.txt into calendar_dates.txt entries. weekly: Convert the active dates of each "
+        "service back to calendar.txt rows where the pattern is regular.",
+    ),
+) -> None:
+    logger.info("################################")
+    logger.info("####### Convert calendar #######")
+    logger.info(f"Input: {input_object}")
+    logger.info(f"To: {to.value}")
+    logger.info("################################")
+    logger.info("####### Start processing #######")
+    _confirm_overwrite(ctx, output_folder)
+    extractor: Extractor = Extractor(
+        input_object=Path(input_object),
+        output_folder=Path(output_folder),
+        cpu_count=ctx.obj.cpu_count,
+        max_output_rows=ctx.obj.max_output_rows,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    files: List = extractor.convert_calendar(to=to)
+    extractor.close()
     logger.info("############ Result ############")
     run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
     logger.info(f"Run time: {run_time}")
@@ -124,7 +643,113 @@ def extract_date(
     logger.info("################################")
 
 
-@app.command()
+@app.command(help="Fill blank trip headsigns from the last stop or the route name.")
+def backfill_headsigns(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_folder: str = typer.Option(..., help="Directory to which the GFTS files are written"),
+    headsign_strategy: HeadsignStrategy = typer.Option(
+        HeadsignStrategy.last_stop,
+        help="Fill blank headsigns with the name of the last stop of the trip or with the route name. The last stop "
+        "falls back to the route name.",
+    ),
+) -> None:
+    logger.info("################################")
+    logger.info("###### Backfill headsigns ######")
+    logger.info(f"Input: {input_object}")
+    logger.info(f"Strategy: {headsign_strategy.value}")
+    logger.info("################################")
+    logger.info("####### Start processing #######")
+    _confirm_overwrite(ctx, output_folder)
+    extractor: Extractor = Extractor(
+        input_object=Path(input_object),
+        output_folder=Path(output_folder),
+        cpu_count=ctx.obj.cpu_count,
+        max_output_rows=ctx.obj.max_output_rows,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    files: List = extractor.backfill_headsigns(strategy=headsign_strategy)
+    extractor.close()
+    logger.info("############ Result ############")
+    run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
+    logger.info(f"Run time: {run_time}")
+    logger.info(f"Processed {len(files)} files:")
+    file: Path
+    for file in files:
+        logger.info(file.__str__())
+    logger.info("################################")
+
+
+@app.command(help="Fill blank stop times by interpolating between the known times of each trip.")
+def interpolate_stop_times(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_folder: str = typer.Option(..., help="Directory to which the GFTS files are written"),
+) -> None:
+    logger.info("################################")
+    logger.info("#### Interpolate stop times ####")
+    logger.info(f"Input: {input_object}")
+    logger.info("################################")
+    logger.info("####### Start processing #######")
+    _confirm_overwrite(ctx, output_folder)
+    extractor: Extractor = Extractor(
+        input_object=Path(input_object),
+        output_folder=Path(output_folder),
+        cpu_count=ctx.obj.cpu_count,
+        max_output_rows=ctx.obj.max_output_rows,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    files: List = extractor.interpolate_stop_times()
+    extractor.close()
+    logger.info("############ Result ############")
+    run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
+    logger.info(f"Run time: {run_time}")
+    logger.info(f"Processed {len(files)} files:")
+    file: Path
+    for file in files:
+        logger.info(file.__str__())
+    logger.info("################################")
+
+
+@app.command(help="Write each GTFS file as newline-delimited JSON with one typed object per row.")
+def export_ndjson(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_folder: str = typer.Option(..., help="Directory to which the NDJSON files are written"),
+) -> None:
+    logger.info("################################")
+    logger.info("######## Export NDJSON #########")
+    logger.info(f"Input: {input_object}")
+    logger.info("################################")
+    logger.info("####### Start processing #######")
+    _confirm_overwrite(ctx, output_folder, suffix=".ndjson")
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    files: List[Path] = gtfs.export_ndjson(Path(output_folder))
+    gtfs.close()
+    logger.info("############ Result ############")
+    run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
+    logger.info(f"Run time: {run_time}")
+    logger.info(f"Exported {len(files)} files:")
+    file: Path
+    for file in files:
+        logger.info(file.__str__())
+    logger.info("################################")
+
+
+@app.command(help="Show the service date window of the feed.")
 def metadata(
     ctx: typer.Context,
     input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
@@ -134,17 +759,466 @@ def metadata(
     logger.info(f"Input: {input_object}")
     logger.info("################################")
     logger.info("####### Start processing #######")
-    gtfs: GTFS = GTFS(input_object=Path(input_object), cpu_count=ctx.obj.cpu_count)
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+        extract_files=("calendar.txt", "calendar_dates.txt"),
+    )
     dates = gtfs.service_date_range()
+    coverage: List[int] = gtfs.weekday_coverage()
     gtfs.close()
     logger.info("############ Result ############")
     run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
     logger.info(f"Run time: {run_time}")
     logger.info(f"Service date window from '{dates[0]}' to '{dates[1]}'")
+    logger.info(f"{'Weekday':<10} {'Service days':>12}")
+    weekday: str
+    service_days: int
+    for weekday, service_days in zip(WEEKDAYS, coverage):
+        logger.info(f"{weekday:<10} {service_days:>12}")
+    logger.info("################################")
+
+
+@app.command(help="Write the service date window and the size of many feeds to one CSV file.")
+def catalog(
+    ctx: typer.Context,
+    input_objects: List[str] = typer.Argument(..., help="Directories or zip Files from which the GFTS files are read"),
+    output: Path = typer.Option(..., help="The CSV file to write the catalog to."),
+) -> None:
+    logger.info("################################")
+    logger.info("######## Catalog feeds #########")
+    logger.info(f"Inputs: {', '.join(input_objects)}")
+    logger.info(f"Output: {output}")
+    logger.info("################################")
+    logger.info("####### Start processing #######")
+    rows: List[Dict] = []
+    input_object: str
+    for input_object in input_objects:
+        row: Dict = {"feed": input_object, "error": None}
+        gtfs: GTFS | None = None
+        try:
+            gtfs = GTFS(
+                input_object=Path(input_object),
+                cpu_count=ctx.obj.cpu_count,
+                null_values=ctx.obj.null_values,
+                temp_dir=ctx.obj.temp_dir,
+                fail_on_warning=ctx.obj.fail_on_warning,
+                io_retries=ctx.obj.io_retries,
+            )
+            row.update(gtfs.feed_summary())
+        except Exception as error:
+            # A broken feed shouldn't stop the catalog, its row records why it failed instead.
+            logger.warning(f"Couldn't catalog {input_object}: {error}")
+            row["error"] = str(error) or type(error).__name__
+        finally:
+            if gtfs is not None:
+                gtfs.close()
+        rows.append(row)
+    catalog_table: pd.DataFrame = pd.DataFrame(
+        rows,
+        columns=["feed", "service_start", "service_end", "agencies", "routes", "trips", "stops", "error"],
+    )
+    # Errored rows have no counts, keep the others integer instead of float
+    counts: List[str] = ["agencies", "routes", "trips", "stops"]
+    catalog_table[counts] = catalog_table[counts].astype("Int64")
+    catalog_table.to_csv(output, index=False)
+    logger.info("############ Result ############")
+    run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
+    logger.info(f"Run time: {run_time}")
+    logger.info(f"Cataloged {len(rows)} feeds, {catalog_table['error'].notna().sum()} of them failed")
+    logger.info("################################")
+
+
+@app.command(help="Show the stops with the most stop times, the hubs of the feed.")
+def busiest_stops(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    n: int = typer.Option(10, "--n", min=1, help="The number of stops to show."),
+    date: Optional[str] = typer.Option(
+        None, callback=_check_date, help="Only count the trips running on this service day. Format: YYYYMMDD"
+    ),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    stops: pd.DataFrame = gtfs.busiest_stops(n, datetime.strptime(date, "%Y%m%d").date() if date else None)
+    gtfs.close()
+    if output_format == OutputFormat.json:
+        typer.echo(stops.to_json(orient="records"))
+        return
+    _echo_table(ctx, stops)
+
+
+@app.command(help="Show the groups of stops that lie within a distance of each other.")
+def clustered_stops(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    meters: float = typer.Option(..., help="Stops up to this many meters apart are grouped."),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+        extract_files=("stops.txt",),
+    )
+    clusters: List[List[str]] = gtfs.find_clustered_stops(meters)
+    gtfs.close()
+    if output_format == OutputFormat.json:
+        typer.echo(json.dumps(clusters))
+        return
+    cluster: List[str]
+    for cluster in clusters:
+        typer.echo(", ".join(cluster))
+
+
+@app.command(help="Show the groups of trips with the same route, service and stop times.")
+def duplicate_trips(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    duplicates: List[List[str]] = gtfs.find_duplicate_trips()
+    gtfs.close()
+    if output_format == OutputFormat.json:
+        typer.echo(json.dumps(duplicates))
+        return
+    group: List[str]
+    for group in duplicates:
+        typer.echo(", ".join(group))
+
+
+@app.command(help="Show the dates on which each service is active.")
+def service_calendar(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    service_id: Optional[str] = typer.Option(None, help="Only show this service. All services by default."),
+    max_dates: int = typer.Option(
+        31, min=0, help="Only list this many dates per service, the number of days is always complete. 0 lists all."
+    ),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+        extract_files=("calendar.txt", "calendar_dates.txt"),
+    )
+    calendar: Dict[str, List[date]] = gtfs.service_calendar()
+    gtfs.close()
+    if service_id is not None:
+        if service_id not in calendar:
+            raise typer.BadParameter(
+                f"{service_id} isn't a service of calendar.txt or calendar_dates.txt", param_hint="--service-id"
+            )
+        calendar = {service_id: calendar[service_id]}
+    services: List[Dict] = [
+        {
+            "service_id": service,
+            "days": len(dates),
+            "dates": [day.strftime("%Y%m%d") for day in (dates[:max_dates] if max_dates else dates)],
+        }
+        for service, dates in sorted(calendar.items())
+    ]
+    if output_format == OutputFormat.json:
+        typer.echo(json.dumps(services))
+        return
+    table: pd.DataFrame = pd.DataFrame(
+        [
+            (
+                service["service_id"],
+                service["days"],
+                ", ".join(service["dates"]) + (", ..." if len(service["dates"]) < service["days"] else ""),
+            )
+            for service in services
+        ],
+        columns=["service_id", "days", "dates"],
+    )
+    _echo_table(ctx, table)
+
+
+@app.command(help="Show per column statistics of a GTFS file.")
+def describe(
+    ctx: typer.Context,
+    file_name: str = typer.Argument(..., metavar="FILE", help="The GTFS file to describe, e.g. stops.txt"),
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+        extract_files=(file_name,),
+    )
+    statistics: pd.DataFrame = gtfs.describe_table(file_name)
+    gtfs.close()
+    if output_format == OutputFormat.json:
+        typer.echo(statistics.to_json(orient="records"))
+        return
+    _echo_table(ctx, statistics)
+
+
+@app.command(help="Estimate the size of an extraction by date range and/or bbox before running it.")
+def estimate_size(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    start_date: Optional[str] = typer.Option(None, callback=_check_date, help="Lower date boundary. Format: YYYYMMDD"),
+    end_date: Optional[str] = typer.Option(
+        None, callback=_check_date, help="Upper date boundary, inclusive. Format: YYYYMMDD"
+    ),
+    bbox: Optional[str] = typer.Option(
+        None,
+        help="The bbox of the extraction. Format is WGS84 Coordinates lon/lat (lon min, lat min, lon max, lat max) "
+        'Example: "8.573179,49.352003,8.79405,49.459693"',
+    ),
+    quote_style: QuoteStyle = typer.Option(QuoteStyle.always, help="Quoting of the output CSV fields."),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    if (start_date is None) != (end_date is None):
+        raise typer.BadParameter("--start-date and --end-date must be given together")
+    if start_date is None and bbox is None:
+        raise typer.BadParameter("Give a date range, a bbox or both")
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    estimates: pd.DataFrame = gtfs.estimate_extract_size(
+        start_date=datetime.strptime(start_date, "%Y%m%d").date() if start_date else None,
+        end_date=datetime.strptime(end_date, "%Y%m%d").date() if end_date else None,
+        bbox=Bbox(*[float(x.strip()) for x in bbox.split(",")]) if bbox else None,
+        quoting=quote_style.quoting,
+    )
+    gtfs.close()
+    if output_format == OutputFormat.json:
+        typer.echo(estimates.to_json(orient="records"))
+        return
+    sizes: pd.DataFrame = estimates.assign(size=estimates["bytes"].map(format_bytes)).drop(columns=["bytes"])
+    _echo_table(ctx, sizes)
+    typer.echo(f"Estimated total: {format_bytes(estimates['bytes'].sum())}")
+
+
+@app.command(help="Show the seconds between the departures of each route and direction on a service day.")
+def headways(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    date: str = typer.Option(..., callback=_check_date, help="The service day to look at. Format: YYYYMMDD"),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    route_headways: pd.DataFrame = gtfs.headways(datetime.strptime(date, "%Y%m%d").date())
+    gtfs.close()
+    if output_format == OutputFormat.json:
+        typer.echo(route_headways.to_json(orient="records"))
+        return
+    _echo_table(ctx, route_headways)
+
+
+@app.command(help="Show the delimiter, byte order mark, encoding, row count and columns of each file.")
+def inspect(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+        check_required_files=False,
+        convert_tab_delimited=False,
+    )
+    reports: List[Dict] = gtfs.inspect_files()
+    gtfs.close()
+    if output_format == OutputFormat.json:
+        typer.echo(json.dumps(reports))
+        return
+    table: pd.DataFrame = pd.DataFrame(reports, columns=["file", "delimiter", "bom", "encoding", "rows", "columns"])
+    table["delimiter"] = table["delimiter"].map(repr)
+    table["columns"] = table["columns"].map(", ".join)
+    _echo_table(ctx, table)
+
+
+@app.command(help="Show the extent of the stops served by each route.")
+def route_bounds(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    bounds: pd.DataFrame = gtfs.route_bounds()
+    gtfs.close()
+    if output_format == OutputFormat.json:
+        typer.echo(bounds.to_json(orient="records"))
+        return
+    _echo_table(ctx, bounds)
+
+
+@app.command(help="Show the most common first and last stop of each route and direction.")
+def route_endpoints(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    endpoints: pd.DataFrame = gtfs.route_endpoints()
+    gtfs.close()
+    if output_format == OutputFormat.json:
+        typer.echo(endpoints.to_json(orient="records"))
+        return
+    _echo_table(ctx, endpoints)
+
+
+@app.command(help="Show the distance in meters of each trip along its shape or its stops.")
+def trip_distances(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    distances: pd.DataFrame = gtfs.trip_distances()
+    gtfs.close()
+    if output_format == OutputFormat.json:
+        typer.echo(distances.to_json(orient="records"))
+        return
+    _echo_table(ctx, distances)
+
+
+@app.command(help="Show the minimum, mean and maximum trip duration in seconds of each route.")
+def trip_durations(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    gtfs: GTFS = GTFS(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    durations: pd.DataFrame = gtfs.trip_durations()
+    gtfs.close()
+    if output_format == OutputFormat.json:
+        typer.echo(durations.to_json(orient="records"))
+        return
+    _echo_table(ctx, durations)
+
+
+@app.command(help="Check the feed for common issues.")
+def validate(
+    ctx: typer.Context,
+    input_object: str = typer.Option(..., help="Directory or zip File from which the GFTS files are read"),
+    self_check: bool = typer.Option(
+        False,
+        "--self-check",
+        hidden=True,
+        help="Also extract the whole service range and check that no schedule rows are lost.",
+    ),
+    report: Optional[Path] = typer.Option(
+        None, help="Write all findings with their check and severity to this JSON file."
+    ),
+    severity: Optional[List[str]] = typer.Option(
+        None,
+        help='Override the severity of a check, e.g. "coordinates=error". Only errors fail the validation, warnings '
+        "are reported. ignore drops the findings of the check. Can be repeated.",
+    ),
+) -> None:
+    severities: Dict[str, Severity] = _severities(severity)
+    logger.info("################################")
+    logger.info("######### Validate feed ########")
+    logger.info(f"Input: {input_object}")
+    logger.info("################################")
+    logger.info("####### Start processing #######")
+    validator: Validator = Validator(
+        input_object=Path(input_object),
+        cpu_count=ctx.obj.cpu_count,
+        null_values=ctx.obj.null_values,
+        temp_dir=ctx.obj.temp_dir,
+        fail_on_warning=ctx.obj.fail_on_warning,
+        io_retries=ctx.obj.io_retries,
+    )
+    validation_report: ValidationReport = validator.validate(severities)
+    if self_check:
+        validation_report.extend("roundtrip", Severity.error, validator.check_roundtrip())
+    validator.close()
+    logger.info("############ Result ############")
+    run_time: str = datetime.utcfromtimestamp(time.time() - script_start_time).strftime("%H:%M:%S.%f")
+    logger.info(f"Run time: {run_time}")
+    finding: Finding
+    for finding in validation_report.findings:
+        logger.warning(f"{finding.severity.value}: {finding.message}")
+    logger.info(f"Found {len(validation_report.findings)} issues")
     logger.info("################################")
+    if report is not None:
+        validation_report.write_json(report)
+    # Warnings are reported but only errors fail the validation
+    if any(finding.severity == Severity.error for finding in validation_report.findings):
+        raise typer.Exit(code=VALIDATION_ERROR)
 
 
-@app.command()
+@app.command(help="Start the web server.")
 def server(
     ctx: typer.Context,
     host: str = typer.Option("0.0.0.0", help="Provide the desired host."),
@@ -170,15 +1244,84 @@ def server(
         StandaloneApplication(create_app(), options).run()
 
 
+@app.command(hidden=True, help="Print the columns and dtypes read from each GTFS file as JSON.")
+def schemas() -> None:
+    typer.echo(json.dumps(GtfsDtypes.describe_all()))
+
+
+@app.command(help="List the available commands.")
+def commands(
+    ctx: typer.Context,
+    output_format: OutputFormat = typer.Option(OutputFormat.text, "--format", help="Print as text or as JSON."),
+) -> None:
+    group: click.Group = cast(click.Group, ctx.find_root().command)
+    available: List[Dict[str, str]] = []
+    name: str
+    for name in group.list_commands(ctx):
+        command: click.Command | None = group.get_command(ctx, name)
+        if command is None or command.hidden:
+            continue
+        available.append({"name": name, "help": command.get_short_help_str(limit=120)})
+    if output_format == OutputFormat.json:
+        typer.echo(json.dumps(available))
+        return
+    width: int = max(len(entry["name"]) for entry in available)
+    entry: Dict[str, str]
+    for entry in available:
+        typer.echo(f"{entry['name']:<{width}}  {entry['help']}")
+
+
 @app.callback()
 def main(
     ctx: typer.Context,
     logging: Optional[str] = "INFO",
+    log_format: LogFormat = typer.Option(
+        LogFormat.text, help="Write the logs to stderr as text or as one JSON object per line."
+    ),
     cores: int = typer.Option(
         cpu_count - 1 if cpu_count else 1,
         help="Set the number of cores to use for processing.",
     ),
     progress: Optional[bool] = typer.Option(True, help="Deactivate the progress bars."),
+    max_output_rows: Optional[int] = typer.Option(
+        None,
+        help="Abort if a single output file would exceed this number of rows. Unlimited by default.",
+    ),
+    null_values: Optional[str] = typer.Option(
+        None,
+        help="Comma separated values that are read as missing in addition to empty fields and the pandas defaults. "
+        'e.g. "unknown,-"',
+    ),
+    temp_dir: Optional[Path] = typer.Option(
+        None,
+        help="Directory in which archives are extracted. Defaults to the system temp directory. The extracted files "
+        "are removed on exit.",
+    ),
+    yes: bool = typer.Option(
+        False,
+        "--yes",
+        "-y",
+        help="Overwrite existing GTFS files in the output folder without asking. Without a terminal to ask on, "
+        "commands refuse to overwrite them unless this is passed.",
+    ),
+    fail_on_warning: bool = typer.Option(
+        False,
+        "--fail-on-warning",
+        help="Abort on problems that are otherwise only logged, e.g. missing optional files, unknown files or "
+        "services that are never active.",
+    ),
+    io_retries: int = typer.Option(
+        0,
+        min=0,
+        help="Retry reading an input archive this many times after transient I/O errors, e.g. on network drives. "
+        "Invalid archives are never retried.",
+    ),
+    pretty: bool = typer.Option(
+        False,
+        "--pretty",
+        help="Print tables with borders and columns fitted to the terminal width. Output that isn't written to a "
+        "terminal stays plain text.",
+    ),
     version: Optional[bool] = typer.Option(
         None,
         "--version",
@@ -192,10 +1335,19 @@ def main(
         tqdm.__init__ = partialmethod(tqdm.__init__, disable=True)
     if logging is None:
         logging = "INFO"
-    initialize_logging(logging)
+    initialize_logging(logging, log_format)
     initialize_dask()
     logger.info("############ Run info ############")
     logger.info(f"Log level: {logging}")
     logger.info(f"Number of cores: {cores}")
-    ctx.obj = Shared(cpu_count=cores)
+    ctx.obj = Shared(
+        cpu_count=cores,
+        max_output_rows=max_output_rows,
+        null_values=[value.strip() for value in null_values.split(",")] if null_values else None,
+        temp_dir=temp_dir,
+        yes=yes,
+        fail_on_warning=fail_on_warning,
+        io_retries=io_retries,
+        pretty=pretty,
+    )
     return
@@ -2,7 +2,7 @@ import pathlib
 import shutil
 import zipfile
 from pathlib import Path
-from typing import Generator
+from typing import Dict, Generator
 
 import pytest
 from _pytest.tmpdir import TempPathFactory
@@ -12,6 +12,20 @@ from gtfs_general.application import create_app
 
 script_path = pathlib.Path(__file__).parent.resolve()
 
+# Headers of the optional files the test feed lacks
+OPTIONAL_FILE_HEADERS: Dict[str, str] = {
+    "areas.txt": "area_id",
+    "booking_rules.txt": "booking_rule_id,booking_type",
+    "fare_attributes.txt": "fare_id,price,currency_type,payment_method,transfers",
+    "fare_rules.txt": "fare_id",
+    "frequencies.txt": "trip_id,start_time,end_time,headway_secs",
+    "levels.txt": "level_id,level_index",
+    "pathways.txt": "pathway_id,from_stop_id,to_stop_id,pathway_mode,is_bidirectional",
+    "stop_areas.txt": "area_id,stop_id",
+    "transfers.txt": "from_stop_id,to_stop_id,transfer_type",
+    "translations.txt": "table_name,field_name,language,translation",
+}
+
 
 @pytest.fixture(scope="module")
 def test_client() -> Generator[TestClient, None, None]:
@@ -29,3 +43,15 @@ def gtfs_test_folder(
         zip_ref.extractall(tmp_path)
     yield tmp_path
     shutil.rmtree(tmp_path)
+
+
+@pytest.fixture(scope="function")
+def gtfs_strict_test_folder(gtfs_test_folder: Path) -> Path:
+    """
+    The test feed with empty optional files, so fail_on_warning doesn't raise because they are missing.
+    """
+    file_name: str
+    header: str
+    for file_name, header in OPTIONAL_FILE_HEADERS.items():
+        gtfs_test_folder.joinpath(file_name).write_text(f"{header}\n")
+    return gtfs_test_folder
@@ -1,12 +1,39 @@
+import codecs
+import json
+import os
 import pathlib
-from typing import List
+import tempfile
+from concurrent.futures import Future, ThreadPoolExecutor
+from datetime import datetime
+from typing import Any, Dict, List, Set
 
+import pandas as pd
+import pytest
 from _pytest._py.path import LocalPath
+from dask import dataframe as ddf
 from typer.testing import CliRunner
 
-from gtfs_general import __app_name__, __version__, main
+from gtfs_general import EMPTY_RESULT_ERROR, OVERWRITE_ERROR, __app_name__, __version__, main
+from gtfs_general.exceptions.extractor_exceptions import (
+    DateRangeOutsideFeed,
+    GtfsColumnMissing,
+    GtfsFileNotFound,
+    GtfsIncompleteException,
+    InvalidPolygon,
+    OutputFolderNotWritable,
+    OutputRowLimitExceeded,
+    UnrepresentableCharacter,
+    WarningAsError,
+    WorkingDirectoryNotWritable,
+)
+from gtfs_general.extractor.bbox import Bbox
+from gtfs_general.extractor.extractor import Extractor
+from gtfs_general.extractor.gtfs import GTFS
+from tests.snapshot import assert_feed_matches_snapshot
 
 runner = CliRunner()
+# Keeps the logs on stderr apart from the output on stdout, e.g. to parse the JSON output
+output_runner = CliRunner(mix_stderr=False)
 
 script_path = pathlib.Path(__file__).parent.resolve()
 
@@ -90,6 +117,230 @@ def test_extract_by_bbox_with_file(tmpdir: LocalPath) -> None:
     check_ic_ice_gtfs_germany_bbox_extraction_results(tmpdir)
 
 
+def test_extract_with_temp_dir(tmpdir: LocalPath) -> None:
+    test_gtfs_file: pathlib.Path = script_path.joinpath("../../files/ic_ice_gtfs_germany.zip")
+    temp_dir: pathlib.Path = pathlib.Path(tmpdir.mkdir("scratch").__str__())
+    output_folder: LocalPath = tmpdir.mkdir("output")
+
+    extractor: Extractor = Extractor(
+        input_object=test_gtfs_file, output_folder=pathlib.Path(output_folder.__str__()), temp_dir=temp_dir
+    )
+    assert extractor._feed_folder.parent == temp_dir
+    assert extractor._feed_folder.joinpath("stops.txt").exists()
+    extractor.close()
+    assert list(temp_dir.iterdir()) == []
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "--temp-dir",
+            temp_dir.__str__(),
+            "extract-bbox",
+            "--input-object",
+            test_gtfs_file.__str__(),
+            "--output-folder",
+            output_folder.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code == 0
+    assert list(temp_dir.iterdir()) == []
+    assert all(file.suffix == ".txt" for file in pathlib.Path(output_folder.__str__()).iterdir())
+    check_file_consistency(output_folder)
+    check_ic_ice_gtfs_germany_bbox_extraction_results(output_folder)
+
+
+def test_extract_with_temp_dir_as_output_folder(tmpdir: LocalPath) -> None:
+    test_gtfs_file: pathlib.Path = script_path.joinpath("../../files/ic_ice_gtfs_germany.zip")
+    # Not created up front, the working directory has to exist before the archive is extracted
+    output_folder: LocalPath = tmpdir.join("output")
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "--temp-dir",
+            output_folder.__str__(),
+            "extract-bbox",
+            "--input-object",
+            test_gtfs_file.__str__(),
+            "--output-folder",
+            output_folder.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code == 0
+    # The extracted archive is removed again, only the written files are left
+    assert all(file.is_file() and file.suffix == ".txt" for file in pathlib.Path(output_folder.__str__()).iterdir())
+    check_file_consistency(output_folder)
+    check_ic_ice_gtfs_germany_bbox_extraction_results(output_folder)
+
+
+def test_extract_without_temp_dir_leaves_working_directory_clean(
+    tmpdir: LocalPath, monkeypatch: pytest.MonkeyPatch
+) -> None:
+    test_gtfs_file: pathlib.Path = script_path.joinpath("../../files/ic_ice_gtfs_germany.zip").resolve()
+    working_directory: pathlib.Path = pathlib.Path(tmpdir.mkdir("cwd").__str__())
+    output_folder: LocalPath = tmpdir.mkdir("output")
+    monkeypatch.chdir(working_directory)
+
+    extractor: Extractor = Extractor(input_object=test_gtfs_file, output_folder=pathlib.Path(output_folder.__str__()))
+    # Archives are extracted to the system temp directory by default, never to the working directory
+    assert extractor._feed_folder.parent == pathlib.Path(tempfile.gettempdir())
+    extractor.close()
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            test_gtfs_file.__str__(),
+            "--output-folder",
+            output_folder.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code == 0
+    assert list(working_directory.iterdir()) == []
+    check_file_consistency(output_folder)
+
+
+def test_extract_concurrently_with_shared_temp_dir(tmpdir: LocalPath) -> None:
+    test_gtfs_file: pathlib.Path = script_path.joinpath("../../files/ic_ice_gtfs_germany.zip")
+    temp_dir: pathlib.Path = pathlib.Path(tmpdir.mkdir("scratch").__str__())
+    output_folders: List[LocalPath] = [tmpdir.mkdir("output_1"), tmpdir.mkdir("output_2")]
+    extractors: List[Extractor] = [
+        Extractor(input_object=test_gtfs_file, output_folder=pathlib.Path(folder.__str__()), temp_dir=temp_dir)
+        for folder in output_folders
+    ]
+    feed_folders: List[pathlib.Path] = [extractor._feed_folder for extractor in extractors]
+    assert feed_folders[0] != feed_folders[1]
+    assert all(folder.parent == temp_dir and folder.name.startswith("ic_ice_gtfs_germany_") for folder in feed_folders)
+
+    with ThreadPoolExecutor(max_workers=2) as executor:
+        futures: List[Future] = [
+            executor.submit(extractor.extract_by_bbox, Bbox(8.573179, 49.352003, 8.79405, 49.459693))
+            for extractor in extractors
+        ]
+        for future in futures:
+            future.result()
+    for extractor in extractors:
+        extractor.close()
+
+    assert list(temp_dir.iterdir()) == []
+    for output_folder in output_folders:
+        check_file_consistency(output_folder)
+        check_ic_ice_gtfs_germany_bbox_extraction_results(output_folder)
+
+
+def test_extract_sequentially_in_one_process(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    # Nothing of an extraction, e.g. the dtypes of the filtered ids, may leak into the next one in the same process
+    output_folders: List[pathlib.Path] = [pathlib.Path(tmpdir.mkdir(name).__str__()) for name in ("first", "second")]
+    bbox: Bbox = Bbox(8.573179, 49.352003, 8.79405, 49.459693)
+    output_folder: pathlib.Path
+    for output_folder in output_folders:
+        extractor: Extractor = Extractor(input_object=gtfs_test_folder, output_folder=output_folder)
+        extractor.extract_by_bbox(bbox)
+        extractor.close()
+        date_folder: pathlib.Path = pathlib.Path(tmpdir.mkdir(f"{output_folder.name}_date").__str__())
+        date_extractor: Extractor = Extractor(input_object=gtfs_test_folder, output_folder=date_folder)
+        date_extractor.extract_by_date(datetime(2022, 10, 4), datetime(2022, 10, 5))
+        date_extractor.close()
+        assert date_extractor.trip_count
+
+    check_file_consistency(tmpdir.join("first"))
+    assert_feed_matches_snapshot(output_folders[1], output_folders[0])
+
+
+def test_extract_by_bbox_keeps_leading_zeros(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    file_name: str
+    column: str
+    for file_name, column in (("stops.txt", "stop_id"), ("stop_times.txt", "stop_id"), ("trips.txt", "route_id")):
+        rows: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath(file_name), dtype=str)
+        rows[column] = "00" + rows[column]
+        rows.to_csv(gtfs_test_folder.joinpath(file_name), index=False)
+    routes: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("routes.txt"), dtype=str)
+    routes["route_id"] = "00" + routes["route_id"]
+    routes.to_csv(gtfs_test_folder.joinpath("routes.txt"), index=False)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code == 0
+    check_ic_ice_gtfs_germany_bbox_extraction_results(tmpdir)
+
+    for file_name, column in (
+        ("stops.txt", "stop_id"),
+        ("stop_times.txt", "stop_id"),
+        ("trips.txt", "route_id"),
+        ("routes.txt", "route_id"),
+    ):
+        output: pd.DataFrame = pd.read_csv(tmpdir.join(file_name), dtype=str)
+        assert output[column].str.startswith("00").all()
+    with open(tmpdir.join("stops.txt"), "r") as fp:
+        assert '"001014"' in fp.read()
+    with open(tmpdir.join("routes.txt"), "r") as fp:
+        assert '"003"' in fp.read()
+
+
+def test_extract_by_bbox_clip_shapes(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    # Trip 1379 stops inside the bbox and uses shape 10001, which leaves the bbox on both ends
+    points: List[List[float]] = [
+        [50.0, 8.0],
+        [49.9, 8.2],
+        [49.5, 8.6],
+        [49.445, 8.668],
+        [49.4, 8.7],
+        [49.2, 8.9],
+        [48.9, 9.2],
+    ]
+    pd.DataFrame(
+        [
+            {"shape_id": "10001", "shape_pt_sequence": sequence, "shape_pt_lat": lat, "shape_pt_lon": lon}
+            for sequence, (lat, lon) in enumerate(points, start=1)
+        ]
+    ).to_csv(gtfs_test_folder.joinpath("shapes.txt"), index=False)
+    arguments: List[str] = [
+        "--logging",
+        "INFO",
+        "--no-progress",
+        "extract-bbox",
+        "--input-object",
+        gtfs_test_folder.__str__(),
+        "--bbox",
+        "8.573179,49.352003,8.79405,49.459693",
+    ]
+
+    full_folder: LocalPath = tmpdir.mkdir("full")
+    result = runner.invoke(main.app, arguments + ["--output-folder", full_folder.__str__()])
+    assert result.exit_code == 0
+    full_shapes: pd.DataFrame = pd.read_csv(full_folder.join("shapes.txt"), dtype={"shape_id": str})
+    assert full_shapes["shape_pt_sequence"].tolist() == [1, 2, 3, 4, 5, 6, 7]
+
+    clipped_folder: LocalPath = tmpdir.mkdir("clipped")
+    result = runner.invoke(main.app, arguments + ["--output-folder", clipped_folder.__str__(), "--clip-shapes"])
+    assert result.exit_code == 0
+    clipped_shapes: pd.DataFrame = pd.read_csv(clipped_folder.join("shapes.txt"), dtype={"shape_id": str})
+    # The two points inside the bbox and one point before and after them are kept
+    assert clipped_shapes[["shape_pt_lat", "shape_pt_lon"]].values.tolist() == points[2:6]
+    assert clipped_shapes["shape_pt_sequence"].tolist() == [0, 1, 2, 3]
+
+
 def test_extract_by_bbox_with_folder(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
     result = runner.invoke(
         main.app,
@@ -125,14 +376,230 @@ def test_get_metadata(gtfs_test_folder: pathlib.Path) -> None:
     )
     assert result.exit_code == 0
     assert "Service date window from '2022-10-02 00:00:00' to '2022-10-09 00:00:00'" in result.stdout
+    assert "saturday" in result.stdout
 
 
-def test_filter_by_date(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+def test_get_metadata_open_ended_service(gtfs_test_folder: pathlib.Path) -> None:
+    calendar_file: pathlib.Path = gtfs_test_folder.joinpath("calendar.txt")
+    calendar: pd.DataFrame = pd.read_csv(calendar_file, dtype=str)
+    calendar.loc[calendar["service_id"] == "68", "end_date"] = "20991231"
+    calendar.to_csv(calendar_file, index=False)
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "metadata", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == 0
+    assert "Service date window from '2022-10-02 00:00:00' to '2099-12-31 00:00:00'" in result.stdout
+    assert "sunday" in result.stdout
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "validate", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == 0
+    assert "Found 0 issues" in result.stdout
+
+
+def test_get_metadata_with_file_extracts_calendar_only(monkeypatch: pytest.MonkeyPatch) -> None:
+    test_gtfs_file: str = script_path.joinpath("../../files/ic_ice_gtfs_germany.zip").__str__()
+    extracted_files: List[str] = []
+    close = GTFS.close
+
+    # The extracted files are removed on close, so look at them right before
+    def close_and_record(gtfs: GTFS) -> None:
+        extracted_files.extend(sorted(file.name for file in gtfs._feed_folder.iterdir()))
+        close(gtfs)
+
+    monkeypatch.setattr(GTFS, "close", close_and_record)
+    result = runner.invoke(main.app, ["--logging", "INFO", "metadata", "--input-object", test_gtfs_file])
+    assert result.exit_code == 0
+    assert "Service date window from '2022-10-02 00:00:00' to '2022-10-09 00:00:00'" in result.stdout
+    assert extracted_files == ["calendar.txt", "calendar_dates.txt"]
+
+
+def test_extract_warns_about_invalid_output(gtfs_strict_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    arguments: List[str] = [
+        "extract-date",
+        "--input-object",
+        gtfs_strict_test_folder.__str__(),
+        "--start-date",
+        "20230101",
+        "--end-date",
+        "20230102",
+    ]
+    result = runner.invoke(main.app, ["--no-progress", *arguments, "--output-folder", tmpdir.__str__()])
+    assert result.exit_code == EMPTY_RESULT_ERROR
+    assert (
+        "The output isn't a valid feed: agency.txt, routes.txt, stops.txt, stop_times.txt, trips.txt without rows"
+        in result.stdout
+    )
+    file_name: str
+    for file_name in ("agency.txt", "routes.txt", "stops.txt", "stop_times.txt", "trips.txt"):
+        assert len(tmpdir.join(file_name).readlines()) == 1
+
+    # In strict mode the invalid feed isn't moved into the output folder
+    strict_folder: LocalPath = tmpdir.mkdir("strict")
+    result = runner.invoke(
+        main.app, ["--no-progress", "--fail-on-warning", *arguments, "--output-folder", strict_folder.__str__()]
+    )
+    assert isinstance(result.exception, WarningAsError)
+    assert result.exception.warning.startswith("The output isn't a valid feed")
+    assert list(pathlib.Path(strict_folder.__str__()).iterdir()) == []
+
+
+def test_extract_by_date_keeps_services_only_added_by_calendar_dates(
+    gtfs_test_folder: pathlib.Path, tmpdir: LocalPath
+) -> None:
+    # A non-numeric service_id that is missing from calendar.txt and only added on a single day of the range
+    calendar_dates_file: pathlib.Path = gtfs_test_folder.joinpath("calendar_dates.txt")
+    calendar_dates: pd.DataFrame = pd.read_csv(calendar_dates_file, dtype=str)
+    addition: pd.DataFrame = pd.DataFrame([{"service_id": "added", "exception_type": "1", "date": "20221004"}])
+    pd.concat([calendar_dates, addition]).to_csv(calendar_dates_file, index=False)
+    trips_file: pathlib.Path = gtfs_test_folder.joinpath("trips.txt")
+    trips: pd.DataFrame = pd.read_csv(trips_file, dtype=str)
+    trips.loc[trips["trip_id"] == "1483", "service_id"] = "added"
+    trips.to_csv(trips_file, index=False)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "extract-date",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--start-date",
+            "20221003",
+            "--end-date",
+            "20221005",
+        ],
+    )
+    assert result.exit_code == 0
+    output_trips: pd.DataFrame = pd.read_csv(tmpdir.join("trips.txt"), dtype=str)
+    assert output_trips[output_trips["trip_id"] == "1483"]["service_id"].tolist() == ["added"]
+    output_stop_times: pd.DataFrame = pd.read_csv(tmpdir.join("stop_times.txt"), dtype=str)
+    assert (output_stop_times["trip_id"] == "1483").any()
+    output_calendar_dates: pd.DataFrame = pd.read_csv(tmpdir.join("calendar_dates.txt"), dtype=str)
+    assert output_calendar_dates[output_calendar_dates["service_id"] == "added"]["date"].tolist() == ["20221004"]
+    assert "added" not in pd.read_csv(tmpdir.join("calendar.txt"), dtype=str)["service_id"].tolist()
+
+
+def test_extract_by_date_append(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    def extract_date(output_folder: LocalPath, day: str, *options: str) -> None:
+        result = runner.invoke(
+            main.app,
+            [
+                "--no-progress",
+                "extract-date",
+                "--input-object",
+                gtfs_test_folder.__str__(),
+                "--output-folder",
+                output_folder.__str__(),
+                "--start-date",
+                day,
+                "--end-date",
+                day,
+                *options,
+            ],
+        )
+        assert result.exit_code == 0
+
+    def service_ids(output_folder: LocalPath) -> Set[str]:
+        return set(pd.read_csv(output_folder.join("calendar.txt"), dtype=str)["service_id"]) | set(
+            pd.read_csv(output_folder.join("calendar_dates.txt"), dtype=str)["service_id"]
+        )
+
+    combined: LocalPath = tmpdir.mkdir("combined")
+    sunday: LocalPath = tmpdir.mkdir("sunday")
+    wednesday: LocalPath = tmpdir.mkdir("wednesday")
+    extract_date(sunday, "20221002")
+    extract_date(wednesday, "20221005")
+    extract_date(combined, "20221002")
+    extract_date(combined, "20221005", "--append")
+
+    check_file_consistency(combined)
+    # Service 77 only runs on the Wednesday
+    assert "77" in service_ids(combined) - service_ids(sunday)
+    assert service_ids(combined) == service_ids(sunday) | service_ids(wednesday)
+    trips: pd.DataFrame = pd.read_csv(combined.join("trips.txt"), dtype=str)
+    assert trips["trip_id"].is_unique
+    assert set(trips["trip_id"]) == set(pd.read_csv(sunday.join("trips.txt"), dtype=str)["trip_id"]) | set(
+        pd.read_csv(wednesday.join("trips.txt"), dtype=str)["trip_id"]
+    )
+    stop_times: pd.DataFrame = pd.read_csv(combined.join("stop_times.txt"), dtype=str)
+    assert not stop_times.duplicated(["trip_id", "stop_sequence"]).any()
+    assert set(stop_times["trip_id"]) == set(trips["trip_id"])
+
+    # The staged rows are UTF-8 while the existing latin1 files are read as latin1
+    latin1: LocalPath = tmpdir.mkdir("latin1")
+    extract_date(latin1, "20221002", "--output-encoding", "latin1")
+    extract_date(latin1, "20221005", "--append", "--output-encoding", "latin1")
+    stops: pd.DataFrame = pd.read_csv(combined.join("stops.txt"), dtype=str).set_index("stop_id")
+    latin1_stops: pd.DataFrame = pd.read_csv(latin1.join("stops.txt"), dtype=str, encoding="latin-1")
+    assert latin1_stops["stop_id"].is_unique
+    assert latin1_stops["stop_name"].str.contains("ü").any()
+    assert latin1_stops["stop_name"].tolist() == stops.loc[latin1_stops["stop_id"], "stop_name"].tolist()
+
+
+def test_extract_by_date_append_trimmed(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    gtfs_test_folder.joinpath("feed_info.txt").unlink()
+
+    def extract_date(day: str, *options: str) -> str:
+        result = runner.invoke(
+            main.app,
+            [
+                "--no-progress",
+                "extract-date",
+                "--input-object",
+                gtfs_test_folder.__str__(),
+                "--output-folder",
+                tmpdir.__str__(),
+                "--start-date",
+                day,
+                "--end-date",
+                day,
+                "--trim-calendar",
+                "--synthesize-feed-info",
+                "--feed-publisher-name",
+                "Example Publisher",
+                "--feed-publisher-url",
+                "https://example.com",
+                *options,
+            ],
+        )
+        assert result.exit_code == 0
+        return result.stdout
+
+    extract_date("20221002")
+    # Trip 1215 of the weekend service 65 changes between both extractions and loses its last stop
+    trips_file: pathlib.Path = gtfs_test_folder.joinpath("trips.txt")
+    trips: pd.DataFrame = pd.read_csv(trips_file, dtype=str)
+    trips.loc[trips["trip_id"] == "1215", "direction_id"] = "1"
+    trips.to_csv(trips_file, index=False)
+    stop_times_file: pathlib.Path = gtfs_test_folder.joinpath("stop_times.txt")
+    stop_times: pd.DataFrame = pd.read_csv(stop_times_file, dtype=str)
+    stop_times = stop_times.drop(stop_times[stop_times["trip_id"] == "1215"].index[-1])
+    stop_times.to_csv(stop_times_file, index=False)
+    output: str = extract_date("20221008", "--append")
+
+    # Both trimmed rows of service 65 are merged into one covering both days
+    calendar: pd.DataFrame = pd.read_csv(tmpdir.join("calendar.txt"), dtype=str)
+    assert calendar.loc[calendar["service_id"] == "65", ["start_date", "end_date"]].values.tolist() == [
+        ["20221002", "20221008"]
+    ]
+    assert calendar["service_id"].is_unique
+    feed_info: pd.DataFrame = pd.read_csv(tmpdir.join("feed_info.txt"), dtype=str)
+    assert feed_info[["feed_start_date", "feed_end_date"]].values.tolist() == [["20221002", "20221008"]]
+    output_trips: pd.DataFrame = pd.read_csv(tmpdir.join("trips.txt"), dtype=str)
+    assert output_trips.loc[output_trips["trip_id"] == "1215", "direction_id"].tolist() == ["1"]
+    assert "1 appended rows of trips.txt replace existing rows with the same trip_id but other values" in output
+    # The stop_times of the replaced trip are replaced as a whole, the dropped stop doesn't stay behind
+    output_stop_times: pd.DataFrame = pd.read_csv(tmpdir.join("stop_times.txt"), dtype=str)
+    assert sorted(output_stop_times.loc[output_stop_times["trip_id"] == "1215", "stop_sequence"]) == sorted(
+        stop_times.loc[stop_times["trip_id"] == "1215", "stop_sequence"]
+    )
+
+
+def test_extract_by_date_rejects_invalid_dates(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
     result = runner.invoke(
         main.app,
         [
-            "--logging",
-            "INFO",
             "--no-progress",
             "extract-date",
             "--input-object",
@@ -140,36 +607,1956 @@ def test_filter_by_date(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> No
             "--output-folder",
             tmpdir.__str__(),
             "--start-date",
+            "2022-13-45",
+            "--end-date",
+            "20221009",
+        ],
+    )
+    assert result.exit_code == 2
+    # The error panel may wrap the message at the terminal width
+    for part in ("'--start-date'", "2022-13-45 isn't a date", "YYYYMMDD"):
+        assert part in result.stdout
+    assert tmpdir.listdir() == []
+
+
+def test_extract_leaves_no_partial_files(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    # calendar.txt is only read after trips.txt, routes.txt, agency.txt and shapes.txt are written
+    calendar_file: pathlib.Path = gtfs_test_folder.joinpath("calendar.txt")
+    calendar: pd.DataFrame = pd.read_csv(calendar_file, dtype=str)
+    calendar.loc[0, "monday"] = "not a number"
+    calendar.to_csv(calendar_file, index=False)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code != 0
+    assert list(pathlib.Path(tmpdir.__str__()).iterdir()) == []
+
+
+def test_extract_fail_on_warning(gtfs_strict_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    arguments: List[str] = [
+        "extract-bbox",
+        "--input-object",
+        gtfs_strict_test_folder.__str__(),
+        "--output-folder",
+        tmpdir.__str__(),
+        "--bbox",
+        "8.573179,49.352003,8.79405,49.459693",
+    ]
+    result = runner.invoke(main.app, ["--no-progress", "--fail-on-warning", *arguments])
+    assert result.exit_code == 0
+
+    # A missing optional file is only logged by default
+    gtfs_strict_test_folder.joinpath("transfers.txt").unlink()
+    result = runner.invoke(main.app, ["--no-progress", "--yes", *arguments])
+    assert result.exit_code == 0
+    result = runner.invoke(main.app, ["--no-progress", "--fail-on-warning", "--yes", *arguments])
+    assert result.exit_code != 0
+    assert isinstance(result.exception, WarningAsError)
+    assert result.exception.warning == "Optional file transfers.txt is missing"
+
+    # fare_rules.txt refers to the fares of the missing fare_attributes.txt
+    gtfs_strict_test_folder.joinpath("fare_attributes.txt").unlink()
+    result = runner.invoke(main.app, ["--no-progress", "--fail-on-warning", "--yes", *arguments])
+    assert result.exit_code != 0
+    assert isinstance(result.exception, WarningAsError)
+    assert result.exception.warning == "fare_attributes.txt is missing, but fare_rules.txt refers to it"
+
+
+def test_extract_project(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    arguments: List[str] = [
+        "--no-progress",
+        "extract-bbox",
+        "--input-object",
+        gtfs_test_folder.__str__(),
+        "--output-folder",
+        tmpdir.__str__(),
+        "--bbox",
+        "8.573179,49.352003,8.79405,49.459693",
+    ]
+    result = runner.invoke(main.app, [*arguments, "--project", "stop_times.txt:trip_id,stop_id,stop_sequence"])
+    assert result.exit_code == 0
+    assert "stop_times.txt is only written with the columns trip_id, stop_id, stop_sequence" in result.stdout
+
+    stop_times: pd.DataFrame = pd.read_csv(tmpdir.join("stop_times.txt"), dtype=str)
+    assert stop_times.columns.tolist() == ["trip_id", "stop_id", "stop_sequence"]
+    assert len(stop_times) == 2233
+    assert "stop_name" in pd.read_csv(tmpdir.join("stops.txt"), dtype=str).columns
+
+    result = runner.invoke(main.app, ["--yes", *arguments, "--project", "stop_times.txt:trip_id,platform"])
+    assert isinstance(result.exception, GtfsColumnMissing)
+    assert result.exception.column == "platform"
+
+    result = runner.invoke(main.app, ["--yes", *arguments, "--project", "required_files.txt:trip_id"])
+    assert isinstance(result.exception, GtfsFileNotFound)
+    assert result.exception.file_path == "required_files.txt"
+
+
+def test_extract_project_read_back_columns(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    # Clipping the shapes and trimming the calendar read the columns the projections drop back from the output
+    clipped_folder: LocalPath = tmpdir.mkdir("clipped")
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            clipped_folder.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+            "--clip-shapes",
+            "--project",
+            "shapes.txt:shape_id,shape_pt_lat,shape_pt_lon",
+        ],
+    )
+    assert result.exit_code == 0
+    shapes: pd.DataFrame = pd.read_csv(clipped_folder.join("shapes.txt"), dtype=str)
+    assert shapes.columns.tolist() == ["shape_id", "shape_pt_lat", "shape_pt_lon"]
+    assert not shapes.empty
+
+    trimmed_folder: LocalPath = tmpdir.mkdir("trimmed")
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "extract-date",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            trimmed_folder.__str__(),
+            "--start-date",
             "20221002",
             "--end-date",
             "20221003",
+            "--trim-calendar",
+            "--project",
+            "calendar.txt:service_id,monday",
         ],
     )
     assert result.exit_code == 0
+    calendar: pd.DataFrame = pd.read_csv(trimmed_folder.join("calendar.txt"), dtype=str)
+    assert calendar.columns.tolist() == ["service_id", "monday"]
+    # Service 65 only starts after the range, so trimming dropped it
+    assert "65" not in set(calendar["service_id"])
+    assert "83" in set(calendar["service_id"])
 
-    check_file_consistency(tmpdir)
 
-    file: pathlib.PosixPath
-    output_files: List = [file for file in pathlib.Path(tmpdir.__str__()).glob("*.txt")]
+def test_extract_sort_output(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    output_folders: List[LocalPath] = [tmpdir.mkdir("first"), tmpdir.mkdir("second")]
+    output_folder: LocalPath
+    for output_folder in output_folders:
+        result = runner.invoke(
+            main.app,
+            [
+                "--no-progress",
+                "extract-bbox",
+                "--input-object",
+                gtfs_test_folder.__str__(),
+                "--output-folder",
+                output_folder.__str__(),
+                "--bbox",
+                "8.573179,49.352003,8.79405,49.459693",
+                "--sort-output",
+            ],
+        )
+        assert result.exit_code == 0
 
-    for file in output_files:
-        with open(file, "r") as fp:
-            x = len(fp.readlines())
-            if file.name == "stop_times.txt":
-                assert x == 5539
-            elif file.name == "stops.txt":
-                assert x == 934
-            elif file.name == "trips.txt":
-                assert x == 540
-            elif file.name == "calendar.txt":
-                assert x == 2
-            elif file.name == "routes.txt":
-                assert x == 73
-            elif file.name == "feed_info.txt":
-                assert x == 2
-            elif file.name == "calendar_dates.txt":
-                assert x == 3
-            elif file.name == "agency.txt":
-                assert x == 10
-            elif file.name == "shapes.txt":
-                assert x == 6
+    first: pathlib.Path = pathlib.Path(output_folders[0].__str__())
+    second: pathlib.Path = pathlib.Path(output_folders[1].__str__())
+    file_name: str
+    for file_name in ("routes.txt", "trips.txt", "stops.txt"):
+        assert first.joinpath(file_name).read_bytes() == second.joinpath(file_name).read_bytes()
+
+    routes: pd.DataFrame = pd.read_csv(first.joinpath("routes.txt"), dtype=str)
+    assert routes["route_id"].tolist() == sorted(routes["route_id"], key=int)
+    trips: pd.DataFrame = pd.read_csv(first.joinpath("trips.txt"), dtype=str)
+    trip_keys: List = [
+        (int(route_id), int(service_id), int(trip_id))
+        for route_id, service_id, trip_id in trips[["route_id", "service_id", "trip_id"]].values
+    ]
+    assert trip_keys == sorted(trip_keys)
+    stops: pd.DataFrame = pd.read_csv(first.joinpath("stops.txt"), dtype=str)
+    assert stops["stop_id"].tolist() == sorted(stops["stop_id"], key=int)
+
+
+def test_extract_filters_levels_pathways_and_translations(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    stops: pd.DataFrame = pd.read_csv(stops_file, dtype=str)
+    stops.loc[stops["stop_id"] == "1014", ["level_id", "parent_station"]] = ["L1", "station"]
+    nodes: pd.DataFrame = pd.DataFrame(
+        [
+            {"stop_name": "Orphan", "stop_id": "orphan", "stop_lat": "49.4", "stop_lon": "8.67", "level_id": "L2"},
+            {
+                "stop_name": "Station",
+                "stop_id": "station",
+                "stop_lat": "49.4",
+                "stop_lon": "8.67",
+                "location_type": "1",
+            },
+            {
+                "stop_name": "Entrance",
+                "stop_id": "entrance",
+                "stop_lat": "49.4",
+                "stop_lon": "8.67",
+                "location_type": "2",
+                "parent_station": "station",
+                "level_id": "L3",
+            },
+        ]
+    )
+    pd.concat([stops, nodes]).to_csv(stops_file, index=False)
+    pd.DataFrame(
+        [
+            {"level_id": "L1", "level_index": "0"},
+            {"level_id": "L2", "level_index": "-1"},
+            {"level_id": "L3", "level_index": "1"},
+        ]
+    ).to_csv(gtfs_test_folder.joinpath("levels.txt"), index=False)
+    pd.DataFrame(
+        [
+            {"pathway_id": "P1", "from_stop_id": "1014", "to_stop_id": "916", "pathway_mode": "1"},
+            {"pathway_id": "P2", "from_stop_id": "1014", "to_stop_id": "orphan", "pathway_mode": "1"},
+            {"pathway_id": "P3", "from_stop_id": "entrance", "to_stop_id": "1014", "pathway_mode": "1"},
+        ]
+    ).assign(is_bidirectional="1").to_csv(gtfs_test_folder.joinpath("pathways.txt"), index=False)
+    translation_columns: List[str] = ["table_name", "field_name", "record_id", "record_sub_id", "field_value"]
+    pd.DataFrame(
+        [
+            ("levels", "level_name", "L1", None, None),
+            ("levels", "level_name", "L2", None, None),
+            ("pathways", "signposted_as", "P1", None, None),
+            ("pathways", "signposted_as", "P2", None, None),
+            ("stops", "stop_name", None, None, "Heidelberg Hbf"),
+            ("stops", "stop_name", None, None, "Orphan"),
+            ("stop_times", "stop_headsign", "1003", "0", None),
+            ("stop_times", "stop_headsign", "1483", "0", None),
+            ("feed_info", "feed_publisher_name", None, None, None),
+        ],
+        columns=translation_columns,
+    ).assign(language="en", translation="Translated").to_csv(
+        gtfs_test_folder.joinpath("translations.txt"), index=False
+    )
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code == 0
+
+    output_folder: pathlib.Path = pathlib.Path(tmpdir.__str__())
+    # The entrance of the station of stop 1014 is kept with its pathway and level
+    assert pd.read_csv(output_folder.joinpath("levels.txt"), dtype=str)["level_id"].tolist() == ["L1", "L3"]
+    assert pd.read_csv(output_folder.joinpath("pathways.txt"), dtype=str)["pathway_id"].tolist() == ["P1", "P3"]
+    output_stop_ids: List[str] = pd.read_csv(output_folder.joinpath("stops.txt"), dtype=str)["stop_id"].tolist()
+    assert {"station", "entrance"} <= set(output_stop_ids)
+    assert "orphan" not in output_stop_ids
+    translations: pd.DataFrame = pd.read_csv(output_folder.joinpath("translations.txt"), dtype=str)
+    assert translations[translation_columns].fillna("").values.tolist() == [
+        ["levels", "level_name", "L1", "", ""],
+        ["pathways", "signposted_as", "P1", "", ""],
+        ["stops", "stop_name", "", "", "Heidelberg Hbf"],
+        ["stop_times", "stop_headsign", "1003", "0", ""],
+        ["feed_info", "feed_publisher_name", "", "", ""],
+    ]
+
+
+def test_extract_by_date_rows(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    extractor: Extractor = Extractor(input_object=gtfs_test_folder, output_folder=pathlib.Path(tmpdir.__str__()))
+    tables: Dict[str, pd.DataFrame] = extractor.extract_by_date_rows(datetime(2022, 10, 2), datetime(2022, 10, 3))
+    extractor.close()
+
+    assert list(pathlib.Path(tmpdir.__str__()).iterdir()) == []
+    assert {"agency.txt", "calendar.txt", "calendar_dates.txt", "routes.txt", "stops.txt", "trips.txt"} <= set(tables)
+    calendar: pd.DataFrame = tables["calendar.txt"]
+    assert len(calendar) == 1
+    assert calendar.loc[0, ["service_id", "start_date", "end_date"]].tolist() == ["46", "20221002", "20221003"]
+    assert set(tables["stop_times.txt"]["trip_id"]) <= set(tables["trips.txt"]["trip_id"])
+
+
+def test_catalog(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    test_gtfs_file: str = script_path.joinpath("../../files/ic_ice_gtfs_germany.zip").__str__()
+    output: pathlib.Path = pathlib.Path(tmpdir.join("catalog.csv").__str__())
+    result = runner.invoke(
+        main.app,
+        ["catalog", gtfs_test_folder.__str__(), test_gtfs_file, "--output", output.__str__()],
+    )
+    assert result.exit_code == 0
+    catalog: pd.DataFrame = pd.read_csv(output, dtype=str)
+    assert len(catalog) == 2
+    assert catalog["feed"].tolist() == [gtfs_test_folder.__str__(), test_gtfs_file]
+    assert catalog["error"].isna().all()
+    assert (catalog["service_start"] == "2022-10-02 00:00:00").all()
+    assert (catalog["service_end"] == "2022-10-09 00:00:00").all()
+    assert catalog[["agencies", "routes", "trips", "stops"]].values.tolist() == [["13", "100", "2776", "1449"]] * 2
+
+    missing_feed: str = tmpdir.join("missing").__str__()
+    result = runner.invoke(
+        main.app,
+        ["catalog", missing_feed, gtfs_test_folder.__str__(), "--output", output.__str__()],
+    )
+    assert result.exit_code == 0
+    catalog = pd.read_csv(output, dtype=str)
+    assert catalog["feed"].tolist() == [missing_feed, gtfs_test_folder.__str__()]
+    assert catalog["error"].notna().tolist() == [True, False]
+    assert catalog["stops"].tolist()[1] == "1449"
+
+
+def test_filter_by_date(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-date",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--start-date",
+            "20221002",
+            "--end-date",
+            "20221003",
+        ],
+    )
+    assert result.exit_code == 0
+
+    check_file_consistency(tmpdir)
+
+    file: pathlib.PosixPath
+    output_files: List = [file for file in pathlib.Path(tmpdir.__str__()).glob("*.txt")]
+
+    for file in output_files:
+        with open(file, "r") as fp:
+            x = len(fp.readlines())
+            if file.name == "stop_times.txt":
+                assert x == 5539
+            elif file.name == "stops.txt":
+                assert x == 934
+            elif file.name == "trips.txt":
+                assert x == 540
+            elif file.name == "calendar.txt":
+                assert x == 2
+            elif file.name == "routes.txt":
+                assert x == 73
+            elif file.name == "feed_info.txt":
+                assert x == 2
+            elif file.name == "calendar_dates.txt":
+                assert x == 3
+            elif file.name == "agency.txt":
+                assert x == 10
+            elif file.name == "shapes.txt":
+                assert x == 6
+
+
+def test_filter_by_date_matches_snapshot(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    # Service 77 only runs on 2022-10-05, regenerate the golden files if an output change is intended
+    extractor: Extractor = Extractor(input_object=gtfs_test_folder, output_folder=pathlib.Path(tmpdir.__str__()))
+    extractor.extract_by_date(datetime(2022, 10, 5), datetime(2022, 10, 5))
+    extractor.close()
+
+    assert_feed_matches_snapshot(
+        pathlib.Path(tmpdir.__str__()), script_path.joinpath("../../files/snapshots/extract_date_20221005")
+    )
+
+
+def test_filter_by_date_end_exclusive(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    inclusive_folder: LocalPath = tmpdir.mkdir("inclusive")
+    exclusive_folder: LocalPath = tmpdir.mkdir("exclusive")
+    for output_folder, extra_args in ((inclusive_folder, []), (exclusive_folder, ["--end-exclusive"])):
+        result = runner.invoke(
+            main.app,
+            [
+                "--logging",
+                "INFO",
+                "--no-progress",
+                "extract-date",
+                "--input-object",
+                gtfs_test_folder.__str__(),
+                "--output-folder",
+                output_folder.__str__(),
+                "--start-date",
+                "20221002",
+                "--end-date",
+                "20221003",
+                *extra_args,
+            ],
+        )
+        assert result.exit_code == 0
+
+    with open(inclusive_folder.join("trips.txt"), "r") as fp:
+        assert len(fp.readlines()) == 540
+    with open(inclusive_folder.join("calendar.txt"), "r") as fp:
+        assert len(fp.readlines()) == 2
+
+    # Service 46 runs through the end date and service 55 is only added on it, both are dropped.
+    with open(exclusive_folder.join("trips.txt"), "r") as fp:
+        assert len(fp.readlines()) == 202
+    with open(exclusive_folder.join("calendar.txt"), "r") as fp:
+        assert len(fp.readlines()) == 1
+    with open(exclusive_folder.join("calendar_dates.txt"), "r") as fp:
+        assert len(fp.readlines()) == 2
+
+
+def test_extractor_read_only_folders(
+    gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path, monkeypatch: pytest.MonkeyPatch
+) -> None:
+    test_gtfs_file: pathlib.Path = script_path.joinpath("../../files/ic_ice_gtfs_germany.zip")
+    output_folder: pathlib.Path = tmp_path.joinpath("read_only")
+    output_folder.mkdir()
+    temp_dir: pathlib.Path = tmp_path.joinpath("scratch")
+    temp_dir.mkdir()
+    read_only: List[pathlib.Path] = [output_folder]
+    # Root ignores directory permissions, so the access check is faked
+    monkeypatch.setattr(os, "access", lambda path, mode: pathlib.Path(path) not in read_only)
+
+    with pytest.raises(OutputFolderNotWritable) as output_error:
+        Extractor(input_object=gtfs_test_folder, output_folder=output_folder)
+    assert output_error.value.directory == output_folder.__str__()
+
+    read_only[:] = [temp_dir]
+    with pytest.raises(WorkingDirectoryNotWritable) as working_directory_error:
+        Extractor(input_object=test_gtfs_file, output_folder=output_folder, temp_dir=temp_dir)
+    assert working_directory_error.value.directory == temp_dir.__str__()
+
+
+def test_filter_by_date_quote_style(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    expected_rows = {
+        "always": '"Vaihingen, Stuttgart","65",',
+        "necessary": '"Vaihingen, Stuttgart",65,',
+        "never": '"Vaihingen, Stuttgart",65,',
+    }
+    for quote_style, expected_row in expected_rows.items():
+        output_folder: LocalPath = tmpdir.mkdir(quote_style)
+        result = runner.invoke(
+            main.app,
+            [
+                "--logging",
+                "INFO",
+                "--no-progress",
+                "extract-date",
+                "--input-object",
+                gtfs_test_folder.__str__(),
+                "--output-folder",
+                output_folder.__str__(),
+                "--start-date",
+                "20221001",
+                "--end-date",
+                "20221031",
+                "--quote-style",
+                quote_style,
+            ],
+        )
+        assert result.exit_code == 0
+        with open(output_folder.join("stops.txt"), "r") as fp:
+            assert expected_row in fp.read()
+
+
+def test_extract_by_bbox_include_files(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+            "--include",
+            "routes.txt",
+            "--include",
+            "trips.txt",
+        ],
+    )
+    assert result.exit_code == 0
+
+    actual_files: List = [file.name for file in pathlib.Path(tmpdir.__str__()).glob("*.txt")]
+    assert "shapes.txt" not in actual_files
+    assert "transfers.txt" not in actual_files
+    assert sorted(actual_files) == sorted(
+        [
+            "agency.txt",
+            "calendar_dates.txt",
+            "calendar.txt",
+            "feed_info.txt",
+            "routes.txt",
+            "stop_times.txt",
+            "stops.txt",
+            "trips.txt",
+        ]
+    )
+
+
+def test_extract_by_bbox_line_endings(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    default_folder: LocalPath = tmpdir.mkdir("default")
+    crlf_folder: LocalPath = tmpdir.mkdir("crlf")
+    for output_folder, extra_args in ((default_folder, []), (crlf_folder, ["--crlf"])):
+        result = runner.invoke(
+            main.app,
+            [
+                "--logging",
+                "INFO",
+                "--no-progress",
+                "extract-bbox",
+                "--input-object",
+                gtfs_test_folder.__str__(),
+                "--output-folder",
+                output_folder.__str__(),
+                "--bbox",
+                "8.573179,49.352003,8.79405,49.459693",
+                *extra_args,
+            ],
+        )
+        assert result.exit_code == 0
+
+    for file in pathlib.Path(default_folder.__str__()).glob("*.txt"):
+        assert b"\r" not in file.read_bytes()
+    for file in pathlib.Path(crlf_folder.__str__()).glob("*.txt"):
+        assert b"\r\n" in file.read_bytes()
+
+
+def test_filter_by_date_clamp_to_feed_info(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-date",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--start-date",
+            "20211101",
+            "--end-date",
+            "20230101",
+            "--clamp-to-feed-info",
+        ],
+    )
+    assert result.exit_code == 0
+    # feed_info.txt declares a window from 20211213 to 20221210
+    assert "Clamped the date range to 2021-12-13 00:00:00 - 2022-12-10 00:00:00" in result.stdout
+    check_file_consistency(tmpdir)
+
+
+def test_filter_by_date_clamp_to_feed_info_outside(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "extract-date",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--start-date",
+            "20230101",
+            "--end-date",
+            "20230201",
+            "--clamp-to-feed-info",
+        ],
+    )
+    assert isinstance(result.exception, DateRangeOutsideFeed)
+    assert "20211213 - 20221210" in str(result.exception)
+    assert not list(pathlib.Path(tmpdir.__str__()).glob("*.txt"))
+
+
+def test_filter_by_date_empty_result(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-date",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--start-date",
+            "20230101",
+            "--end-date",
+            "20230131",
+        ],
+    )
+    assert result.exit_code == EMPTY_RESULT_ERROR
+    assert "Extraction matched 0 trips" in result.stdout
+
+    output_files: List = [file for file in pathlib.Path(tmpdir.__str__()).glob("*.txt")]
+    assert len(output_files) > 0
+    for file in output_files:
+        with open(file, "r") as fp:
+            lines: List[str] = fp.readlines()
+        if file.name == "feed_info.txt":
+            assert len(lines) == 2
+        else:
+            assert len(lines) == 1
+
+
+def test_filter_by_date_headers_without_reading_output(
+    gtfs_test_folder: pathlib.Path, tmpdir: LocalPath, monkeypatch: pytest.MonkeyPatch
+) -> None:
+    read_files: List[pathlib.Path] = []
+    read_csv = ddf.read_csv
+
+    def record_read_csv(path: pathlib.Path, *args: Any, **kwargs: Any) -> ddf.DataFrame:
+        read_files.append(pathlib.Path(path))
+        return read_csv(path, *args, **kwargs)
+
+    monkeypatch.setattr(ddf, "read_csv", record_read_csv)
+    for output_name, start_date, end_date, has_rows in (
+        ("empty", datetime(2023, 1, 1), datetime(2023, 1, 31), False),
+        ("non_empty", datetime(2022, 10, 2), datetime(2022, 10, 3), True),
+    ):
+        output_folder: pathlib.Path = pathlib.Path(tmpdir.mkdir(output_name).__str__())
+        extractor: Extractor = Extractor(input_object=gtfs_test_folder, output_folder=output_folder)
+        extractor.extract_by_date(start_date, end_date)
+        extractor.close()
+
+        assert not any(file.parent == output_folder for file in read_files)
+        assert (extractor.trip_count != 0) == has_rows
+        for file in output_folder.glob("*.txt"):
+            input_columns: List[str] = pd.read_csv(gtfs_test_folder.joinpath(file.name), nrows=0).columns.tolist()
+            assert pd.read_csv(file, nrows=0).columns.tolist() == input_columns
+
+
+def test_filter_by_date_keeps_flex_stop_times_columns(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    stop_times_file: pathlib.Path = gtfs_test_folder.joinpath("stop_times.txt")
+    stop_times: pd.DataFrame = pd.read_csv(stop_times_file, dtype=str)
+    flex_trip: pd.Series = stop_times["trip_id"] == "1483"
+    stop_times.loc[flex_trip, "start_pickup_drop_off_window"] = "08:00:00"
+    stop_times.loc[flex_trip, "end_pickup_drop_off_window"] = "20:00:00"
+    stop_times.loc[flex_trip, "pickup_booking_rule_id"] = "booking_1"
+    stop_times.to_csv(stop_times_file, index=False)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-date",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--start-date",
+            "20221001",
+            "--end-date",
+            "20221031",
+        ],
+    )
+    assert result.exit_code == 0
+
+    output: pd.DataFrame = pd.read_csv(tmpdir.join("stop_times.txt"), dtype=str)
+    flex_rows: pd.DataFrame = output[output["trip_id"] == "1483"]
+    assert len(flex_rows) == flex_trip.sum()
+    assert (flex_rows["start_pickup_drop_off_window"] == "08:00:00").all()
+    assert (flex_rows["end_pickup_drop_off_window"] == "20:00:00").all()
+    assert (flex_rows["pickup_booking_rule_id"] == "booking_1").all()
+
+
+def test_extract_by_bbox_filters_booking_rules(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    stop_times_file: pathlib.Path = gtfs_test_folder.joinpath("stop_times.txt")
+    stop_times: pd.DataFrame = pd.read_csv(stop_times_file, dtype=str)
+    # Trip 1003 lies in the bounding box, trip 1483 doesn't
+    stop_times.loc[stop_times["trip_id"] == "1003", "pickup_booking_rule_id"] = "booking_1"
+    stop_times.loc[stop_times["trip_id"] == "1483", "drop_off_booking_rule_id"] = "booking_2"
+    stop_times.to_csv(stop_times_file, index=False)
+    pd.DataFrame(
+        [
+            {"booking_rule_id": "booking_1", "booking_type": "1", "prior_notice_duration_min": "30"},
+            {"booking_rule_id": "booking_2", "booking_type": "1", "prior_notice_duration_min": "60"},
+            {"booking_rule_id": "unused", "booking_type": "0", "prior_notice_duration_min": None},
+        ]
+    ).to_csv(gtfs_test_folder.joinpath("booking_rules.txt"), index=False)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code == 0
+
+    booking_rules: pd.DataFrame = pd.read_csv(tmpdir.join("booking_rules.txt"), dtype=str)
+    assert booking_rules["booking_rule_id"].tolist() == ["booking_1"]
+    assert booking_rules["prior_notice_duration_min"].tolist() == ["30"]
+
+
+def test_convert_calendar(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    dates_folder: LocalPath = tmpdir.mkdir("dates")
+    weekly_folder: LocalPath = tmpdir.mkdir("weekly")
+    for input_folder, output_folder, to in (
+        (gtfs_test_folder.__str__(), dates_folder, "dates"),
+        (dates_folder.__str__(), weekly_folder, "weekly"),
+    ):
+        result = runner.invoke(
+            main.app,
+            [
+                "--logging",
+                "INFO",
+                "--no-progress",
+                "convert-calendar",
+                "--input-object",
+                input_folder,
+                "--output-folder",
+                output_folder.__str__(),
+                "--to",
+                to,
+            ],
+        )
+        assert result.exit_code == 0
+        check_file_consistency(output_folder)
+
+    # Service 83 runs Tuesday to Sunday from Sunday 20221002 to Sunday 20221009
+    calendar_dates: pd.DataFrame = pd.read_csv(dates_folder.join("calendar_dates.txt"), dtype=str)
+    assert calendar_dates[calendar_dates["service_id"] == "83"]["date"].tolist() == [
+        "20221002",
+        "20221004",
+        "20221005",
+        "20221006",
+        "20221007",
+        "20221008",
+        "20221009",
+    ]
+    assert (calendar_dates["exception_type"] == "1").all()
+    with open(dates_folder.join("calendar.txt"), "r") as fp:
+        assert len(fp.readlines()) == 1
+
+    calendar: pd.DataFrame = pd.read_csv(weekly_folder.join("calendar.txt"), dtype=str)
+    assert calendar[calendar["service_id"] == "83"].values.tolist() == [
+        ["0", "1", "1", "1", "1", "1", "1", "20221002", "20221009", "83"]
+    ]
+
+
+def test_backfill_headsigns(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    stop_times: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("stop_times.txt"), dtype={"trip_id": str})
+    last_stop_id: str = str(
+        stop_times[stop_times["trip_id"] == "1483"].sort_values("stop_sequence")["stop_id"].iloc[-1]
+    )
+    stops: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("stops.txt"), dtype=str)
+    routes: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("routes.txt"), dtype=str)
+    trips: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("trips.txt"), dtype=str)
+    route_id: str = trips[trips["trip_id"] == "1483"]["route_id"].iloc[0]
+    expected_headsigns: Dict[str, str] = {
+        "last-stop": stops[stops["stop_id"] == last_stop_id]["stop_name"].iloc[0],
+        "route-name": routes[routes["route_id"] == route_id]["route_long_name"].iloc[0],
+    }
+
+    for strategy, expected_headsign in expected_headsigns.items():
+        output_folder: LocalPath = tmpdir.mkdir(strategy)
+        result = runner.invoke(
+            main.app,
+            [
+                "--logging",
+                "INFO",
+                "--no-progress",
+                "backfill-headsigns",
+                "--input-object",
+                gtfs_test_folder.__str__(),
+                "--output-folder",
+                output_folder.__str__(),
+                "--headsign-strategy",
+                strategy,
+            ],
+        )
+        assert result.exit_code == 0
+        check_file_consistency(output_folder)
+        output_trips: pd.DataFrame = pd.read_csv(output_folder.join("trips.txt"), dtype=str)
+        assert output_trips["trip_headsign"].notna().all()
+        assert output_trips[output_trips["trip_id"] == "1483"]["trip_headsign"].iloc[0] == expected_headsign
+
+
+def test_extract_by_bbox_max_output_rows(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "--max-output-rows",
+            "1000",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code != 0
+    assert isinstance(result.exception, OutputRowLimitExceeded)
+    assert result.exception.file_name == "stop_times.txt"
+    assert not tmpdir.join("stop_times.txt").exists()
+
+
+def test_extract_by_bbox_null_values(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    stops: pd.DataFrame = pd.read_csv(stops_file, dtype=str)
+    # Neither token is one of the pandas default missing values
+    stops.loc[stops["stop_id"] == "318", "stop_lat"] = "unknown"
+    stops.loc[stops["stop_id"] == "385", "stop_lat"] = "-"
+    stops.to_csv(stops_file, index=False)
+    arguments: List[str] = [
+        "extract-bbox",
+        "--input-object",
+        gtfs_test_folder.__str__(),
+        "--output-folder",
+        tmpdir.__str__(),
+        "--bbox",
+        "8.573179,49.352003,8.79405,49.459693",
+    ]
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "--no-progress"] + arguments)
+    assert result.exit_code != 0
+
+    result = runner.invoke(
+        main.app, ["--logging", "INFO", "--no-progress", "--yes", "--null-values", "unknown, -"] + arguments
+    )
+    assert result.exit_code == 0
+    check_file_consistency(tmpdir)
+    with open(tmpdir.join("stop_times.txt"), "r") as fp:
+        assert len(fp.readlines()) == 2234
+
+
+def test_extract_by_bbox_existing_output_needs_confirmation(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    tmpdir.join("stops.txt").write("stop_id\n")
+    arguments: List[str] = [
+        "extract-bbox",
+        "--input-object",
+        gtfs_test_folder.__str__(),
+        "--output-folder",
+        tmpdir.__str__(),
+        "--bbox",
+        "8.573179,49.352003,8.79405,49.459693",
+    ]
+
+    # The test runner has no terminal to ask on, so the command must refuse instead of waiting for an answer
+    result = runner.invoke(main.app, ["--logging", "INFO", "--no-progress"] + arguments)
+    assert result.exit_code == OVERWRITE_ERROR
+    assert tmpdir.join("stops.txt").read() == "stop_id\n"
+    assert not tmpdir.join("stop_times.txt").exists()
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "--no-progress", "--yes"] + arguments)
+    assert result.exit_code == 0
+    check_file_consistency(tmpdir)
+    check_ic_ice_gtfs_germany_bbox_extraction_results(tmpdir)
+
+    # Files that aren't written by the command don't need a confirmation
+    other_folder: LocalPath = tmpdir.mkdir("other")
+    other_folder.join("README.txt").write("Extracted feeds\n")
+    arguments[arguments.index("--output-folder") + 1] = other_folder.__str__()
+    result = runner.invoke(main.app, ["--logging", "INFO", "--no-progress"] + arguments)
+    assert result.exit_code == 0
+    assert other_folder.join("README.txt").read() == "Extracted feeds\n"
+    check_ic_ice_gtfs_germany_bbox_extraction_results(other_folder)
+
+
+def test_extract_by_bbox_single_agency_without_agency_id(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    agency_file: pathlib.Path = gtfs_test_folder.joinpath("agency.txt")
+    agency: pd.DataFrame = pd.read_csv(agency_file, dtype=str)
+    agency.head(1).drop(columns=["agency_id"]).to_csv(agency_file, index=False)
+    routes_file: pathlib.Path = gtfs_test_folder.joinpath("routes.txt")
+    pd.read_csv(routes_file, dtype=str).drop(columns=["agency_id"]).to_csv(routes_file, index=False)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code == 0
+    check_file_consistency(tmpdir)
+    output_agency: pd.DataFrame = pd.read_csv(tmpdir.join("agency.txt"), dtype=str)
+    assert output_agency["agency_name"].tolist() == agency["agency_name"].head(1).tolist()
+
+
+def test_extract_by_bbox_preserve_extra_files(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    gtfs_test_folder.joinpath("LICENSE").write_text("CC BY 4.0\n")
+    gtfs_test_folder.joinpath("attributions.txt").write_text("organization_name,route_id\nDB,1\n")
+    arguments: List[str] = [
+        "--logging",
+        "INFO",
+        "--no-progress",
+        "extract-bbox",
+        "--input-object",
+        gtfs_test_folder.__str__(),
+        "--bbox",
+        "8.573179,49.352003,8.79405,49.459693",
+    ]
+    default_folder: LocalPath = tmpdir.mkdir("default")
+    result = runner.invoke(main.app, arguments + ["--output-folder", default_folder.__str__()])
+    assert result.exit_code == 0
+    assert not default_folder.join("LICENSE").exists()
+
+    preserved_folder: LocalPath = tmpdir.mkdir("preserved")
+    result = runner.invoke(
+        main.app, arguments + ["--output-folder", preserved_folder.__str__(), "--preserve-extra-files"]
+    )
+    assert result.exit_code == 0
+    check_file_consistency(preserved_folder)
+    assert preserved_folder.join("LICENSE").read() == "CC BY 4.0\n"
+    # GTFS tables that aren't filtered would refer to removed routes
+    assert not preserved_folder.join("attributions.txt").exists()
+
+
+def test_extract_by_bbox_dedupe_stop_times(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    stop_times_file: pathlib.Path = gtfs_test_folder.joinpath("stop_times.txt")
+    stop_times: pd.DataFrame = pd.read_csv(stop_times_file, dtype=str)
+    duplicate: pd.DataFrame = stop_times[stop_times["trip_id"] == "1003"].head(1).assign(stop_headsign="Duplicate")
+    pd.concat([stop_times, duplicate]).to_csv(stop_times_file, index=False)
+    arguments: List[str] = [
+        "--logging",
+        "INFO",
+        "--no-progress",
+        "extract-bbox",
+        "--input-object",
+        gtfs_test_folder.__str__(),
+        "--bbox",
+        "8.573179,49.352003,8.79405,49.459693",
+    ]
+
+    for output_folder, dedupe, expected_lines in (("default", [], 2235), ("deduped", ["--dedupe-stop-times"], 2234)):
+        result = runner.invoke(main.app, arguments + ["--output-folder", tmpdir.join(output_folder).__str__()] + dedupe)
+        assert result.exit_code == 0
+        with open(tmpdir.join(output_folder, "stop_times.txt"), "r") as fp:
+            assert len(fp.readlines()) == expected_lines
+
+    output_stop_times: pd.DataFrame = pd.read_csv(tmpdir.join("deduped", "stop_times.txt"), dtype=str)
+    assert "Duplicate" not in output_stop_times["stop_headsign"].tolist()
+
+
+def test_filter_by_date_without_calendar_dates_matches(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    calendar_dates_file: pathlib.Path = gtfs_test_folder.joinpath("calendar_dates.txt")
+    pd.read_csv(calendar_dates_file, dtype=str).head(0).to_csv(calendar_dates_file, index=False)
+    calendar: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("calendar.txt"), dtype=str)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-date",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--start-date",
+            "20221002",
+            "--end-date",
+            "20221009",
+        ],
+    )
+    assert result.exit_code == 0
+    check_file_consistency(tmpdir)
+    output_calendar: pd.DataFrame = pd.read_csv(tmpdir.join("calendar.txt"), dtype=str)
+    assert set(output_calendar["service_id"]) == set(calendar["service_id"])
+    output_trips: pd.DataFrame = pd.read_csv(tmpdir.join("trips.txt"), dtype=str)
+    assert set(output_trips["service_id"]) <= set(calendar["service_id"])
+    assert len(output_trips) > 0
+
+
+def test_extract_by_bbox_fare_areas(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    pd.DataFrame(
+        [
+            {"area_id": "heidelberg", "area_name": "Heidelberg"},
+            {"area_id": "nowhere", "area_name": "Nowhere"},
+            {"area_id": "unused", "area_name": "Unused"},
+        ]
+    ).to_csv(gtfs_test_folder.joinpath("areas.txt"), index=False)
+    pd.DataFrame(
+        [
+            {"area_id": "heidelberg", "stop_id": "1014"},
+            {"area_id": "heidelberg", "stop_id": "unknown_stop"},
+            {"area_id": "nowhere", "stop_id": "unknown_stop"},
+        ]
+    ).to_csv(gtfs_test_folder.joinpath("stop_areas.txt"), index=False)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code == 0
+    stops: pd.DataFrame = pd.read_csv(tmpdir.join("stops.txt"), dtype=str)
+    stop_areas: pd.DataFrame = pd.read_csv(tmpdir.join("stop_areas.txt"), dtype=str)
+    areas: pd.DataFrame = pd.read_csv(tmpdir.join("areas.txt"), dtype=str)
+    assert stop_areas.values.tolist() == [["heidelberg", "1014"]]
+    assert set(stop_areas["stop_id"]) <= set(stops["stop_id"])
+    assert areas["area_id"].tolist() == ["heidelberg"]
+
+
+@pytest.mark.parametrize("on_missing_shape", ["keep", "clear", "drop-trip"])
+def test_extract_by_bbox_on_missing_shape(
+    gtfs_test_folder: pathlib.Path, tmpdir: LocalPath, on_missing_shape: str
+) -> None:
+    trips_file: pathlib.Path = gtfs_test_folder.joinpath("trips.txt")
+    trips: pd.DataFrame = pd.read_csv(trips_file, dtype=str)
+    trips.loc[trips["trip_id"] == "1003", ["service_id", "shape_id"]] = ["only_1003", "missing_shape"]
+    trips.to_csv(trips_file, index=False)
+    # Give trip 1003 a service of its own, which must go together with the trip
+    calendar_file: pathlib.Path = gtfs_test_folder.joinpath("calendar.txt")
+    calendar: pd.DataFrame = pd.read_csv(calendar_file, dtype=str)
+    own_service: pd.DataFrame = calendar[calendar["service_id"] == "53"].assign(service_id="only_1003")
+    pd.concat([calendar, own_service]).to_csv(calendar_file, index=False)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+            "--on-missing-shape",
+            on_missing_shape,
+        ],
+    )
+    assert result.exit_code == 0
+    output_trips: pd.DataFrame = pd.read_csv(tmpdir.join("trips.txt"), dtype=str)
+    output_stop_times: pd.DataFrame = pd.read_csv(tmpdir.join("stop_times.txt"), dtype=str)
+    output_calendar: pd.DataFrame = pd.read_csv(tmpdir.join("calendar.txt"), dtype=str)
+    trip: pd.DataFrame = output_trips[output_trips["trip_id"] == "1003"]
+    assert ("only_1003" in set(output_calendar["service_id"])) == (on_missing_shape != "drop-trip")
+    if on_missing_shape == "keep":
+        assert trip["shape_id"].tolist() == ["missing_shape"]
+    elif on_missing_shape == "clear":
+        assert trip["shape_id"].isna().all()
+        assert len(trip) == 1
+    else:
+        assert trip.empty
+        assert "1003" not in output_stop_times["trip_id"].tolist()
+        assert len(output_trips) == 145
+
+
+def test_extract_by_bbox_max_routes(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+            "--max-routes",
+            "3",
+        ],
+    )
+    assert result.exit_code == 0
+    check_file_consistency(tmpdir)
+    output_trips: pd.DataFrame = pd.read_csv(tmpdir.join("trips.txt"), dtype=str)
+    output_routes: pd.DataFrame = pd.read_csv(tmpdir.join("routes.txt"), dtype=str)
+    output_stop_times: pd.DataFrame = pd.read_csv(tmpdir.join("stop_times.txt"), dtype=str)
+    # 18 routes stop inside the bbox, the first three by route_id are kept
+    assert set(output_trips["route_id"]) == {"11", "25", "26"}
+    assert set(output_routes["route_id"]) == set(output_trips["route_id"])
+    assert set(output_stop_times["trip_id"]) == set(output_trips["trip_id"])
+    assert len(output_trips) < 146
+
+
+@pytest.mark.parametrize("route_type_option", [["--exclude-route-type", "2"], ["--only-route-type", "3"]])
+def test_extract_by_bbox_route_types(
+    gtfs_test_folder: pathlib.Path, tmpdir: LocalPath, route_type_option: List[str]
+) -> None:
+    # All routes of the fixture are rail, make three of the routes inside the bbox buses
+    routes_file: pathlib.Path = gtfs_test_folder.joinpath("routes.txt")
+    routes: pd.DataFrame = pd.read_csv(routes_file, dtype=str)
+    routes.loc[routes["route_id"].isin(["11", "25", "26"]), "route_type"] = "3"
+    routes.to_csv(routes_file, index=False)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+            *route_type_option,
+        ],
+    )
+    assert result.exit_code == 0
+    check_file_consistency(tmpdir)
+    output_routes: pd.DataFrame = pd.read_csv(tmpdir.join("routes.txt"), dtype=str)
+    output_trips: pd.DataFrame = pd.read_csv(tmpdir.join("trips.txt"), dtype=str)
+    output_stop_times: pd.DataFrame = pd.read_csv(tmpdir.join("stop_times.txt"), dtype=str)
+    output_stops: pd.DataFrame = pd.read_csv(tmpdir.join("stops.txt"), dtype=str)
+    assert set(output_routes["route_type"]) == {"3"}
+    assert set(output_trips["route_id"]) == {"11", "25", "26"}
+    assert set(output_stop_times["trip_id"]) == set(output_trips["trip_id"])
+    assert set(output_stops["stop_id"]) == set(output_stop_times["stop_id"])
+
+
+def test_route_types_in_memory_and_projected(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    routes_file: pathlib.Path = gtfs_test_folder.joinpath("routes.txt")
+    routes: pd.DataFrame = pd.read_csv(routes_file, dtype=str)
+    routes.loc[routes["route_id"].isin(["11", "25", "26"]), "route_type"] = "3"
+    routes.to_csv(routes_file, index=False)
+
+    # An in-memory extraction has no trips.txt to read back
+    extractor: Extractor = Extractor(
+        input_object=gtfs_test_folder, output_folder=pathlib.Path(tmpdir.__str__()), only_route_types=[3]
+    )
+    tables: Dict[str, pd.DataFrame] = extractor.extract_by_date_rows(datetime(2022, 10, 2), datetime(2022, 10, 9))
+    extractor.close()
+    assert set(tables["trips.txt"]["route_id"]) == {"11", "25", "26"}
+    assert set(tables["stop_times.txt"]["trip_id"]) == set(tables["trips.txt"]["trip_id"])
+    assert set(tables["calendar.txt"]["service_id"]) <= set(tables["trips.txt"]["service_id"]) | set(
+        tables["calendar_dates.txt"]["service_id"]
+    )
+
+    # The projected trips.txt has neither service_id nor shape_id
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+            "--exclude-route-type",
+            "2",
+            "--project",
+            "trips.txt:trip_id,route_id",
+        ],
+    )
+    assert result.exit_code == 0
+    output_trips: pd.DataFrame = pd.read_csv(tmpdir.join("trips.txt"), dtype=str)
+    assert output_trips.columns.tolist() == ["trip_id", "route_id"]
+    assert set(output_trips["route_id"]) == {"11", "25", "26"}
+    output_stop_times: pd.DataFrame = pd.read_csv(tmpdir.join("stop_times.txt"), dtype=str)
+    assert set(output_stop_times["trip_id"]) == set(output_trips["trip_id"])
+
+
+def test_extract_by_bbox_keeps_optional_stop_columns(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    stops: pd.DataFrame = pd.read_csv(stops_file, dtype=str)
+    optional_columns: Dict[str, str] = {
+        "stop_code": "007",
+        "tts_stop_name": "Heidelberg Hauptbahnhof",
+        "zone_id": "0100",
+        "level_id": "L0",
+        "platform_code": "05",
+    }
+    for column, value in optional_columns.items():
+        stops[column] = None
+        stops.loc[stops["stop_id"] == "1014", column] = value
+    stops.to_csv(stops_file, index=False)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code == 0
+    output_stops: pd.DataFrame = pd.read_csv(tmpdir.join("stops.txt"), dtype=str)
+    assert set(stops.columns) <= set(output_stops.columns)
+    assert output_stops[output_stops["stop_id"] == "1014"][list(optional_columns)].to_dict("records") == [
+        optional_columns
+    ]
+
+
+def test_filter_by_date_trim_calendar(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    # Let service 83 span the whole month and keep it through an addition within the extraction window
+    calendar_file: pathlib.Path = gtfs_test_folder.joinpath("calendar.txt")
+    calendar: pd.DataFrame = pd.read_csv(calendar_file, dtype=str)
+    calendar.loc[calendar["service_id"] == "83", ["start_date", "end_date"]] = ["20221001", "20221031"]
+    # Service 65 only has a removal within the window, so nothing keeps it running once its row is dropped
+    calendar.loc[calendar["service_id"] == "65", ["start_date", "end_date"]] = ["20221101", "20221130"]
+    calendar.to_csv(calendar_file, index=False)
+    with open(gtfs_test_folder.joinpath("calendar_dates.txt"), "a") as fp:
+        fp.write("83,1,20221003\n65,2,20221002\n")
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-date",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--start-date",
+            "20221002",
+            "--end-date",
+            "20221003",
+            "--trim-calendar",
+        ],
+    )
+    assert result.exit_code == 0
+
+    output: pd.DataFrame = pd.read_csv(tmpdir.join("calendar.txt"), dtype=str)
+    assert output[output["service_id"] == "83"].values.tolist() == [
+        ["0", "1", "1", "1", "1", "1", "1", "20221002", "20221003", "83"]
+    ]
+    assert (output["start_date"] >= "20221002").all()
+    assert (output["end_date"] <= "20221003").all()
+    assert "65" not in set(output["service_id"])
+    assert "The services 65 have no calendar_dates.txt additions in the date range" in result.stdout
+
+
+def test_get_metadata_json_logs(gtfs_test_folder: pathlib.Path) -> None:
+    result = output_runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--log-format",
+            "json",
+            "metadata",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+        ],
+    )
+    assert result.exit_code == 0
+
+    log_lines: List = [json.loads(line) for line in result.stderr.splitlines() if line.startswith("{")]
+    assert len(log_lines) > 0
+    assert all({"timestamp", "level", "message"} <= log_line.keys() for log_line in log_lines)
+    assert any(
+        log_line["message"] == "Service date window from '2022-10-02 00:00:00' to '2022-10-09 00:00:00'"
+        and log_line["level"] == "INFO"
+        for log_line in log_lines
+    )
+
+
+def test_list_commands() -> None:
+    result = runner.invoke(main.app, ["--logging", "INFO", "commands"])
+    assert result.exit_code == 0
+    for command in ("metadata", "extract-date", "extract-bbox"):
+        assert command in result.stdout
+
+    result = output_runner.invoke(main.app, ["--logging", "INFO", "commands", "--format", "json"])
+    assert result.exit_code == 0
+    commands: List[str] = [command["name"] for command in json.loads(result.stdout)]
+    assert {"metadata", "extract-date", "extract-bbox"} <= set(commands)
+    assert "server" in commands
+
+
+def test_schemas() -> None:
+    result = output_runner.invoke(main.app, ["--logging", "INFO", "schemas"])
+    assert result.exit_code == 0
+    schemas: Dict[str, Dict[str, str]] = json.loads(result.stdout)
+    assert schemas["stops.txt"]["stop_lat"] == "float64"
+    assert schemas["stops.txt"]["stop_id"] == "str"
+    assert schemas["stop_times.txt"]["stop_sequence"] == "Int64"
+    assert set(schemas) >= {"agency.txt", "calendar.txt", "levels.txt", "translations.txt"}
+
+    result = output_runner.invoke(main.app, ["--logging", "INFO", "commands", "--format", "json"])
+    assert "schemas" not in [command["name"] for command in json.loads(result.stdout)]
+
+
+def test_service_calendar(gtfs_test_folder: pathlib.Path) -> None:
+    calendar: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("calendar.txt"), dtype=str)
+    service: pd.Series = calendar.set_index("service_id").loc["65"]
+    weekdays: List[str] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"]
+    # Service 65 has no calendar_dates.txt exceptions, so its dates follow the weekdays of its calendar.txt row
+    expected_dates: List[str] = [
+        day.strftime("%Y%m%d")
+        for day in pd.date_range(service["start_date"], service["end_date"])
+        if service[weekdays[day.weekday()]] == "1"
+    ]
+    result = output_runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "service-calendar",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--service-id",
+            "65",
+            "--format",
+            "json",
+        ],
+    )
+    assert result.exit_code == 0
+    services: List[Dict] = json.loads(result.stdout)
+    assert services == [{"service_id": "65", "days": len(expected_dates), "dates": expected_dates}]
+    assert expected_dates == ["20221002", "20221008", "20221009"]
+
+    result = runner.invoke(
+        main.app,
+        ["--logging", "INFO", "service-calendar", "--input-object", gtfs_test_folder.__str__(), "--max-dates", "1"],
+    )
+    assert result.exit_code == 0
+    assert "20221002, ..." in result.stdout
+
+
+def test_describe_stops(gtfs_test_folder: pathlib.Path) -> None:
+    stops: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("stops.txt"))
+    result = output_runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "describe",
+            "stops.txt",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--format",
+            "json",
+        ],
+    )
+    assert result.exit_code == 0
+    statistics: pd.DataFrame = pd.DataFrame(json.loads(result.stdout)).set_index("column")
+    assert statistics.loc["stop_lat", "min"] == pytest.approx(stops["stop_lat"].min())
+    assert statistics.loc["stop_lat", "max"] == pytest.approx(stops["stop_lat"].max())
+    assert statistics.loc["stop_id", "distinct"] == 1449
+    assert pd.isna(statistics.loc["stop_name", "min"])
+
+
+def test_describe_stops_pretty(gtfs_test_folder: pathlib.Path, monkeypatch: pytest.MonkeyPatch) -> None:
+    arguments: List[str] = ["--pretty", "describe", "stops.txt", "--input-object", gtfs_test_folder.__str__()]
+    # The test runner doesn't write to a terminal, so the table stays plain text
+    result = runner.invoke(main.app, arguments)
+    assert result.exit_code == 0
+    assert "│" not in result.stdout
+
+    monkeypatch.setattr(main, "_stdout_is_terminal", lambda: True)
+    result = runner.invoke(main.app, arguments)
+    assert result.exit_code == 0
+    rows: List[str] = [line for line in result.stdout.splitlines() if line.startswith("│")]
+    assert len(rows) >= 4
+    assert any(line.lstrip("│ ").startswith("stop_lat") for line in rows)
+    # All rows share the column borders
+    borders: List[List[int]] = [[index for index, char in enumerate(line) if char == "│"] for line in rows]
+    assert all(border == borders[0] for border in borders)
+
+
+def test_inspect(gtfs_test_folder: pathlib.Path) -> None:
+    arguments: List[str] = ["--logging", "INFO", "inspect", "--input-object", gtfs_test_folder.__str__(), "--format"]
+    result = output_runner.invoke(main.app, arguments + ["json"])
+    assert result.exit_code == 0
+    reports: Dict[str, Dict] = {report["file"]: report for report in json.loads(result.stdout)}
+    assert sorted(reports) == sorted(file.name for file in gtfs_test_folder.glob("*.txt"))
+    assert all(report["delimiter"] == "," and not report["bom"] for report in reports.values())
+    assert reports["stops.txt"]["rows"] == 1449
+    assert reports["stops.txt"]["columns"] == ["stop_name", "stop_id", "stop_lat", "stop_lon"]
+    assert reports["stops.txt"]["encoding"] == "utf-8"
+    assert reports["trips.txt"]["encoding"] == "ascii"
+
+    calendar_dates_file: pathlib.Path = gtfs_test_folder.joinpath("calendar_dates.txt")
+    calendar_dates_file.write_bytes(codecs.BOM_UTF8 + calendar_dates_file.read_bytes().replace(b",", b";"))
+    result = output_runner.invoke(main.app, arguments + ["json"])
+    assert result.exit_code == 0
+    reports = {report["file"]: report for report in json.loads(result.stdout)}
+    report: Dict = reports["calendar_dates.txt"]
+    assert report["delimiter"] == ";"
+    assert report["bom"]
+    assert report["columns"] == ["service_id", "exception_type", "date"]
+
+    result = runner.invoke(main.app, arguments + ["text"])
+    assert result.exit_code == 0
+    assert "stop_name, stop_id, stop_lat, stop_lon" in result.stdout
+
+
+def test_extract_by_bbox_latin1(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    arguments: List[str] = [
+        "--logging",
+        "INFO",
+        "--no-progress",
+        "extract-bbox",
+        "--input-object",
+        gtfs_test_folder.__str__(),
+        "--bbox",
+        "8.573179,49.352003,8.79405,49.459693",
+        "--output-encoding",
+        "latin1",
+        "--output-folder",
+    ]
+    result = runner.invoke(main.app, arguments + [tmpdir.join("latin1").__str__()])
+    assert result.exit_code == 0
+    input_stops: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("stops.txt"), dtype=str).set_index("stop_id")
+    output_stops: pd.DataFrame = pd.read_csv(
+        tmpdir.join("latin1", "stops.txt"), dtype=str, encoding="latin-1"
+    ).set_index("stop_id")
+    assert output_stops["stop_name"].str.contains("ü").any()
+    assert output_stops["stop_name"].equals(input_stops.loc[output_stops.index, "stop_name"])
+    with pytest.raises(UnicodeDecodeError):
+        tmpdir.join("latin1", "stops.txt").read_text("utf-8")
+
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    input_stops["stop_name"] = input_stops["stop_name"] + " €"
+    input_stops.reset_index().to_csv(stops_file, index=False)
+    result = runner.invoke(main.app, arguments + [tmpdir.join("strict").__str__()])
+    assert isinstance(result.exception, UnrepresentableCharacter)
+    assert result.exception.file_name == "stops.txt"
+    assert not tmpdir.join("strict", "stops.txt").exists()
+
+    result = runner.invoke(main.app, arguments + [tmpdir.join("lossy").__str__(), "--lossy"])
+    assert result.exit_code == 0
+    lossy_stops: pd.DataFrame = pd.read_csv(tmpdir.join("lossy", "stops.txt"), dtype=str, encoding="latin-1")
+    assert lossy_stops["stop_name"].str.endswith(" ?").all()
+
+
+def test_extract_tab_delimited_file(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    agency_file: pathlib.Path = gtfs_test_folder.joinpath("agency.txt")
+    agencies: pd.DataFrame = pd.read_csv(agency_file, dtype=str)
+    agencies.to_csv(agency_file, sep="\t", index=False)
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+        ],
+    )
+    assert result.exit_code == 0
+    check_file_consistency(tmpdir)
+    with open(tmpdir.join("agency.txt"), "r") as fp:
+        header: str = fp.readline()
+    assert "\t" not in header and header.startswith(",".join(agencies.columns))
+    output_agencies: pd.DataFrame = pd.read_csv(tmpdir.join("agency.txt"), dtype=str)
+    assert set(output_agencies["agency_id"]) <= set(agencies["agency_id"])
+    # The input folder is left as it is
+    assert "\t" in agency_file.read_text().splitlines()[0]
+
+
+def test_extract_multiple_ranges(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    ranges: List = [
+        (datetime(2022, 10, 2), datetime(2022, 10, 3), pathlib.Path(tmpdir.join("first").__str__())),
+        (datetime(2022, 10, 2), datetime(2022, 10, 9), pathlib.Path(tmpdir.join("second").__str__())),
+    ]
+    extractor: Extractor = Extractor(input_object=gtfs_test_folder, output_folder=pathlib.Path(tmpdir.__str__()))
+    results: List[List] = extractor.extract_multiple_ranges(ranges)
+    extractor.close()
+    assert len(results) == 2
+
+    start_date: datetime
+    end_date: datetime
+    output_folder: pathlib.Path
+    for start_date, end_date, output_folder in ranges:
+        single_folder: pathlib.Path = output_folder.with_name(f"{output_folder.name}_single")
+        single_extractor: Extractor = Extractor(input_object=gtfs_test_folder, output_folder=single_folder)
+        single_extractor.extract_by_date(start_date, end_date)
+        single_extractor.close()
+        check_file_consistency(LocalPath(output_folder))
+        for file_name in ("trips.txt", "stop_times.txt", "stops.txt", "calendar.txt", "calendar_dates.txt"):
+            assert output_folder.joinpath(file_name).read_text() == single_folder.joinpath(file_name).read_text()
+
+    with open(tmpdir.join("first", "trips.txt"), "r") as fp:
+        assert len(fp.readlines()) == 540
+
+
+def test_filter_by_date_synthesize_feed_info(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    gtfs_test_folder.joinpath("feed_info.txt").unlink()
+    # feed_info.txt is only optional if it is synthesized
+    with pytest.raises(GtfsIncompleteException):
+        Extractor(input_object=gtfs_test_folder, output_folder=pathlib.Path(tmpdir))
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-date",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--start-date",
+            "20221002",
+            "--end-date",
+            "20221004",
+            "--end-exclusive",
+            "--synthesize-feed-info",
+            "--feed-publisher-name",
+            "Example Publisher",
+            "--feed-publisher-url",
+            "https://example.com",
+        ],
+    )
+    assert result.exit_code == 0
+    check_file_consistency(tmpdir)
+    feed_info: pd.DataFrame = pd.read_csv(tmpdir.join("feed_info.txt"), dtype=str)
+    assert feed_info.to_dict("records") == [
+        {
+            "feed_publisher_name": "Example Publisher",
+            "feed_publisher_url": "https://example.com",
+            "feed_lang": "de",
+            "feed_start_date": "20221002",
+            "feed_end_date": "20221003",
+        }
+    ]
+
+
+def test_extract_by_bbox_output_prefix(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            "8.573179,49.352003,8.79405,49.459693",
+            "--output-prefix",
+            "2022w40_",
+        ],
+    )
+    assert result.exit_code == 0
+    output_folder: pathlib.Path = pathlib.Path(tmpdir.__str__())
+    expected_files: List[str] = sorted(f"2022w40_{file.name}" for file in gtfs_test_folder.glob("*.txt"))
+    assert sorted(file.name for file in output_folder.glob("*.txt")) == expected_files
+    with open(output_folder.joinpath("2022w40_stop_times.txt"), "r") as fp:
+        assert len(fp.readlines()) == 2234
+
+    # Prefixed files aren't GTFS tables for consumers that expect the canonical names
+    with pytest.raises(GtfsIncompleteException):
+        GTFS(input_object=output_folder)
+    with GTFS(input_object=output_folder, check_required_files=False) as gtfs:
+        assert sorted(gtfs.get_filenames()) == expected_files
+        with pytest.raises(GtfsFileNotFound):
+            gtfs.read_table("stops.txt")
+
+
+def test_extract_agencies(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-agencies",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--agency-id",
+            "6",
+        ],
+    )
+    assert result.exit_code == 0
+    check_file_consistency(tmpdir)
+    agency: pd.DataFrame = pd.read_csv(tmpdir.join("agency.txt"), dtype=str)
+    assert agency["agency_name"].tolist() == ["DB Fernverkehr AG"]
+    routes: pd.DataFrame = pd.read_csv(tmpdir.join("routes.txt"), dtype=str)
+    assert len(routes) == 65
+    assert set(routes["agency_id"]) == {"6"}
+    trips: pd.DataFrame = pd.read_csv(tmpdir.join("trips.txt"), dtype=str)
+    assert set(trips["route_id"]) <= set(routes["route_id"])
+    stop_times: pd.DataFrame = pd.read_csv(tmpdir.join("stop_times.txt"), dtype=str)
+    assert set(stop_times["trip_id"]) == set(trips["trip_id"])
+
+
+def test_extract_agencies_single_agency(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    agency_file: pathlib.Path = gtfs_test_folder.joinpath("agency.txt")
+    agency: pd.DataFrame = pd.read_csv(agency_file, dtype=str)
+    agency[agency["agency_id"] == "6"].to_csv(agency_file, index=False)
+    # A route without agency_id belongs to the sole agency, the routes of other agencies don't
+    routes_file: pathlib.Path = gtfs_test_folder.joinpath("routes.txt")
+    input_routes: pd.DataFrame = pd.read_csv(routes_file, dtype=str)
+    blank_route_id: str = input_routes.loc[input_routes["agency_id"] == "3", "route_id"].iloc[0]
+    input_routes.loc[input_routes["route_id"] == blank_route_id, "agency_id"] = None
+    input_routes.to_csv(routes_file, index=False)
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-agencies",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--agency-id",
+            "6",
+        ],
+    )
+    assert result.exit_code == 0
+    routes: pd.DataFrame = pd.read_csv(tmpdir.join("routes.txt"), dtype=str)
+    assert len(routes) == 66
+    assert set(routes["agency_id"].fillna("")) == {"6", ""}
+    assert blank_route_id in set(routes["route_id"])
+
+
+def test_extract_distance(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    arguments: List[str] = [
+        "--logging",
+        "INFO",
+        "--no-progress",
+        "extract-distance",
+        "--input-object",
+        gtfs_test_folder.__str__(),
+        "--min-distance",
+        "2000",
+        "--max-distance",
+        "5000",
+    ]
+    result = runner.invoke(main.app, arguments + ["--output-folder", tmpdir.join("without_distances").__str__()])
+    assert result.exit_code != 0
+    assert isinstance(result.exception, GtfsColumnMissing)
+
+    # Only trip 1003 has distances, its stops are 1000 m apart
+    stop_times_file: pathlib.Path = gtfs_test_folder.joinpath("stop_times.txt")
+    stop_times: pd.DataFrame = pd.read_csv(stop_times_file, dtype=str)
+    trip_rows: pd.Series = stop_times["trip_id"] == "1003"
+    stop_times.loc[trip_rows, "shape_dist_traveled"] = stop_times.loc[trip_rows, "stop_sequence"].astype(int) * 1000
+    stop_times.to_csv(stop_times_file, index=False)
+
+    output_folder: LocalPath = tmpdir.mkdir("with_distances")
+    result = runner.invoke(main.app, arguments + ["--output-folder", output_folder.__str__()])
+    assert result.exit_code == 0
+    output_stop_times: pd.DataFrame = pd.read_csv(output_folder.join("stop_times.txt"), dtype={"trip_id": str})
+    assert output_stop_times["trip_id"].unique().tolist() == ["1003"]
+    assert output_stop_times["stop_sequence"].tolist() == [2, 3, 4, 5]
+    assert output_stop_times["shape_dist_traveled"].between(2000, 5000).all()
+    output_trips: pd.DataFrame = pd.read_csv(output_folder.join("trips.txt"), dtype=str)
+    assert output_trips["trip_id"].tolist() == ["1003"]
+    output_stops: pd.DataFrame = pd.read_csv(output_folder.join("stops.txt"), dtype=str)
+    assert sorted(output_stops["stop_id"]) == sorted(
+        set(stop_times.loc[trip_rows & stop_times["stop_sequence"].isin(["2", "3", "4", "5"]), "stop_id"])
+    )
+
+
+def test_interpolate_stop_times(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    stop_times_file: pathlib.Path = gtfs_test_folder.joinpath("stop_times.txt")
+    stop_times: pd.DataFrame = pd.read_csv(stop_times_file, dtype=str)
+    sequences: pd.Series = stop_times["stop_sequence"].astype(int)
+    # Trip 1003 is interpolated by distance, trip 1483 has no distances and is spaced evenly
+    stop_times["shape_dist_traveled"] = (sequences * 1000.0).where(stop_times["trip_id"] == "1003")
+    stop_times.loc[(stop_times["trip_id"] == "1003") & (sequences == 6), "shape_dist_traveled"] = 6500.0
+    blank: pd.Series = ((stop_times["trip_id"] == "1003") & (sequences == 6)) | (
+        (stop_times["trip_id"] == "1483") & sequences.isin([0, 2])
+    )
+    stop_times.loc[blank, ["arrival_time", "departure_time"]] = None
+    stop_times.to_csv(stop_times_file, index=False)
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "interpolate-stop-times",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+        ],
+    )
+    assert result.exit_code == 0
+    check_file_consistency(tmpdir)
+    output_stop_times: pd.DataFrame = pd.read_csv(tmpdir.join("stop_times.txt"), dtype=str)
+    assert len(output_stop_times) == len(stop_times)
+    output_stop_times = output_stop_times.set_index(["trip_id", "stop_sequence"])
+    # Between the departure 12:44:00 and the arrival 13:01:00 at 1500 of 2000 meters
+    assert output_stop_times.loc[("1003", "6"), ["arrival_time", "departure_time"]].tolist() == ["12:56:45"] * 2
+    # Halfway between the departure 19:28:00 and the arrival 19:40:00
+    assert output_stop_times.loc[("1483", "2"), ["arrival_time", "departure_time"]].tolist() == ["19:34:00"] * 2
+    # Leading blanks have no earlier time to interpolate from
+    assert output_stop_times.loc[("1483", "0"), ["arrival_time", "departure_time"]].isna().all()
+    assert output_stop_times.loc[("1003", "5"), "departure_time"] == "12:44:00"
+
+
+def test_export_ndjson(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "export-ndjson",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+        ],
+    )
+    assert result.exit_code == 0
+    assert sorted(file.basename for file in tmpdir.listdir()) == sorted(
+        f"{file.stem}.ndjson" for file in gtfs_test_folder.glob("*.txt")
+    )
+    with open(tmpdir.join("stops.ndjson"), "r", encoding="utf-8") as fp:
+        lines: List[str] = fp.read().splitlines()
+    assert len(lines) == len(pd.read_csv(gtfs_test_folder.joinpath("stops.txt")))
+    stop: Dict
+    for stop in map(json.loads, lines[:5]):
+        assert isinstance(stop["stop_lat"], float)
+        assert isinstance(stop["stop_lon"], float)
+        assert isinstance(stop["stop_id"], str)
+    with open(tmpdir.join("stop_times.ndjson"), "r", encoding="utf-8") as fp:
+        assert sum(1 for _ in fp) == len(pd.read_csv(gtfs_test_folder.joinpath("stop_times.txt")))
+
+
+def test_estimate_size(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    bbox: str = "8.573179,49.352003,8.79405,49.459693"
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-bbox",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--bbox",
+            bbox,
+        ],
+    )
+    assert result.exit_code == 0
+    actual_bytes: int = sum(file.stat().st_size for file in pathlib.Path(tmpdir.__str__()).glob("*.txt"))
+
+    result = output_runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "estimate-size",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--bbox",
+            bbox,
+            "--format",
+            "json",
+        ],
+    )
+    assert result.exit_code == 0
+    estimates: List[Dict[str, Any]] = json.loads(result.stdout)
+    rows: Dict[str, int] = {estimate["file"]: estimate["rows"] for estimate in estimates}
+    assert rows["trips.txt"] == 146
+    assert rows["stop_times.txt"] == 2233
+    estimated_bytes: int = sum(estimate["bytes"] for estimate in estimates)
+    assert actual_bytes / 2 <= estimated_bytes <= actual_bytes * 2
+
+    result = runner.invoke(main.app, ["estimate-size", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code != 0
+
+
+def test_extract_sample_is_reproducible(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    def sample(output_folder: str, seed: List[str]) -> pd.DataFrame:
+        result = runner.invoke(
+            main.app,
+            [
+                "--logging",
+                "INFO",
+                "--no-progress",
+                "extract-sample",
+                "--input-object",
+                gtfs_test_folder.__str__(),
+                "--output-folder",
+                tmpdir.join(output_folder).__str__(),
+                "--trips-per-route",
+                "2",
+            ]
+            + seed,
+        )
+        assert result.exit_code == 0
+        check_file_consistency(tmpdir.join(output_folder))
+        return pd.read_csv(tmpdir.join(output_folder, "trips.txt"), dtype=str)
+
+    first_trips: pd.DataFrame = sample("first", ["--seed", "42"])
+    sample("second", ["--seed", "42"])
+    assert_feed_matches_snapshot(
+        pathlib.Path(tmpdir.join("second").__str__()), pathlib.Path(tmpdir.join("first").__str__())
+    )
+    assert first_trips.groupby("route_id").size().max() == 2
+    assert first_trips["route_id"].nunique() == 100
+
+    other_trips: pd.DataFrame = sample("other", ["--seed", "7"])
+    assert set(other_trips["trip_id"]) != set(first_trips["trip_id"])
+    default_trips: pd.DataFrame = sample("default", [])
+    assert default_trips.equals(sample("default_again", []))
+
+    # pandas only takes seeds that fit into 32 bits, larger ones are rejected as usage errors
+    sample("largest", ["--seed", str(2**32 - 1)])
+    result = runner.invoke(
+        main.app,
+        [
+            "--no-progress",
+            "extract-sample",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.join("too_large").__str__(),
+            "--trips-per-route",
+            "2",
+            "--seed",
+            str(2**32),
+        ],
+    )
+    assert result.exit_code == 2
+    assert not tmpdir.join("too_large").exists()
+
+
+def test_extract_by_trip_ids(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    trip_ids: List[str] = ["1003", "1483"]
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "extract-trips",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--output-folder",
+            tmpdir.__str__(),
+            "--trip-id",
+            trip_ids[0],
+            "--trip-id",
+            trip_ids[1],
+            "--trip-id",
+            "unknown",
+        ],
+    )
+    assert result.exit_code == 0
+    check_file_consistency(tmpdir)
+
+    def read(folder: Any, file_name: str) -> pd.DataFrame:
+        return pd.read_csv(pathlib.Path(folder.__str__()).joinpath(file_name), dtype=str)
+
+    input_trips: pd.DataFrame = read(gtfs_test_folder, "trips.txt")
+    input_trips = input_trips[input_trips["trip_id"].isin(trip_ids)]
+    input_stop_times: pd.DataFrame = read(gtfs_test_folder, "stop_times.txt")
+    input_stop_times = input_stop_times[input_stop_times["trip_id"].isin(trip_ids)]
+    input_routes: pd.DataFrame = read(gtfs_test_folder, "routes.txt")
+    input_routes = input_routes[input_routes["route_id"].isin(input_trips["route_id"])]
+
+    assert sorted(read(tmpdir, "trips.txt")["trip_id"]) == trip_ids
+    assert len(read(tmpdir, "stop_times.txt")) == len(input_stop_times)
+    assert set(read(tmpdir, "stops.txt")["stop_id"]) == set(input_stop_times["stop_id"])
+    assert set(read(tmpdir, "routes.txt")["route_id"]) == set(input_trips["route_id"])
+    assert set(read(tmpdir, "agency.txt")["agency_id"]) == set(input_routes["agency_id"])
+    assert set(read(tmpdir, "calendar.txt")["service_id"]) <= set(input_trips["service_id"])
+    assert "unknown are missing from trips.txt" in result.stdout
+
+
+def test_extract_by_polygon(gtfs_test_folder: pathlib.Path, tmpdir: LocalPath) -> None:
+    # A triangle around the Heidelberg Hbf stops 1014 and 916 that leaves out the other platforms a few meters away
+    triangle: List[List[float]] = [[8.6749, 49.40335], [8.6751, 49.40335], [8.6749, 49.40355], [8.6749, 49.40335]]
+    geojson: LocalPath = tmpdir.join("area.geojson")
+    feature: Dict[str, Any] = {"type": "Feature", "geometry": {"type": "Polygon", "coordinates": [triangle]}}
+    geojson.write(json.dumps({"type": "FeatureCollection", "features": [feature]}))
+    output_folder: LocalPath = tmpdir.mkdir("output")
+    arguments: List[str] = [
+        "--logging",
+        "INFO",
+        "--no-progress",
+        "extract-polygon",
+        "--input-object",
+        gtfs_test_folder.__str__(),
+        "--output-folder",
+        output_folder.__str__(),
+        "--geojson",
+    ]
+    result = runner.invoke(main.app, arguments + [geojson.__str__()])
+    assert result.exit_code == 0
+    check_file_consistency(output_folder)
+    assert "Found 2 stops in polygon" in result.stdout
+
+    stop_times: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("stop_times.txt"), dtype=str)
+    expected_trip_ids: List[str] = sorted(set(stop_times[stop_times["stop_id"].isin(["1014", "916"])]["trip_id"]))
+    output_trips: pd.DataFrame = pd.read_csv(output_folder.join("trips.txt"), dtype=str)
+    assert sorted(output_trips["trip_id"]) == expected_trip_ids
+    output_stops: pd.DataFrame = pd.read_csv(output_folder.join("stops.txt"), dtype=str)
+    assert {"1014", "916"} <= set(output_stops["stop_id"])
+
+    point: LocalPath = tmpdir.join("point.geojson")
+    point.write(json.dumps({"type": "Point", "coordinates": [8.6749, 49.40335]}))
+    result = runner.invoke(main.app, arguments + [point.__str__()])
+    assert isinstance(result.exception, InvalidPolygon)
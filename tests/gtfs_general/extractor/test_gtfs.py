@@ -0,0 +1,487 @@
+import pathlib
+import shutil
+import tarfile
+import zipfile
+from datetime import date, timedelta
+from typing import Dict, List
+
+import numpy as np
+import pandas as pd
+import pytest
+
+from gtfs_general.exceptions.extractor_exceptions import (
+    DuplicateArchiveMember,
+    GtfsFileNotFound,
+    GtfsIncompleteException,
+    UnreadableArchiveMember,
+    UnsupportedInputFile,
+    WarningAsError,
+)
+from gtfs_general.extractor import gtfs as gtfs_module
+from gtfs_general.extractor.gtfs import GTFS, MAX_SERVICE_DAYS
+from gtfs_general.extractor.utils import EARTH_RADIUS_METERS, time_to_seconds
+from gtfs_general.extractor.validator import Validator
+
+
+def test_get_filenames_skips_directory_entries(gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path) -> None:
+    test_gtfs_file: pathlib.Path = tmp_path.joinpath("with_directory.zip")
+    with zipfile.ZipFile(test_gtfs_file, "w") as zip_ref:
+        zip_ref.writestr("extra/", "")
+        for file in gtfs_test_folder.glob("*.txt"):
+            zip_ref.write(file, arcname=file.name)
+
+    with GTFS(input_object=test_gtfs_file) as gtfs:
+        filenames: List[str] = gtfs.get_filenames()
+
+    assert "extra/" not in filenames
+    assert "extra" not in filenames
+    assert sorted(filenames) == sorted(file.name for file in gtfs_test_folder.glob("*.txt"))
+
+
+def test_get_filenames_flattens_nested_prefix(gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path) -> None:
+    test_gtfs_file: pathlib.Path = tmp_path.joinpath("nested.zip")
+    with zipfile.ZipFile(test_gtfs_file, "w") as zip_ref:
+        zip_ref.writestr("feed/", "")
+        for file in gtfs_test_folder.glob("*.txt"):
+            zip_ref.write(file, arcname=f"feed/{file.name}")
+
+    with GTFS(input_object=test_gtfs_file) as gtfs:
+        filenames: List[str] = gtfs.get_filenames()
+
+    assert "stops.txt" in filenames
+    assert all("/" not in filename for filename in filenames)
+
+
+def test_archive_with_duplicate_file_names(gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path) -> None:
+    test_gtfs_file: pathlib.Path = tmp_path.joinpath("duplicates.zip")
+    with zipfile.ZipFile(test_gtfs_file, "w") as zip_ref:
+        for file in gtfs_test_folder.glob("*.txt"):
+            zip_ref.write(file, arcname=f"a/{file.name}")
+        zip_ref.write(gtfs_test_folder.joinpath("stops.txt"), arcname="b/stops.txt")
+
+    with pytest.raises(DuplicateArchiveMember) as error:
+        GTFS(input_object=test_gtfs_file)
+    assert error.value.file_name == "stops.txt"
+    assert error.value.members == ["a/stops.txt", "b/stops.txt"]
+
+
+def test_get_filenames_skips_unreadable_entries(gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path) -> None:
+    test_gtfs_file: pathlib.Path = tmp_path.joinpath("mixed.zip")
+    with zipfile.ZipFile(test_gtfs_file, "w") as zip_ref:
+        for file in gtfs_test_folder.glob("*.txt"):
+            zip_ref.write(file, arcname=file.name)
+    # Corrupt the local header of a single entry, the central directory stays intact
+    with zipfile.ZipFile(test_gtfs_file, "r") as zip_ref:
+        header_offset: int = zip_ref.getinfo("shapes.txt").header_offset
+    with open(test_gtfs_file, "r+b") as fp:
+        fp.seek(header_offset)
+        fp.write(b"XXXX")
+
+    with GTFS(input_object=test_gtfs_file, extract_files=("calendar.txt",)) as gtfs:
+        filenames: List[str] = gtfs.get_filenames()
+    assert sorted(filenames) == sorted(
+        file.name for file in gtfs_test_folder.glob("*.txt") if file.name != "shapes.txt"
+    )
+
+    with pytest.raises(WarningAsError) as error:
+        GTFS(input_object=test_gtfs_file, extract_files=("calendar.txt",), fail_on_warning=True)
+    assert "shapes.txt" in error.value.warning
+
+    # Extracting the feed without its shapes.txt would write shape_ids without shapes
+    with pytest.raises(UnreadableArchiveMember) as unreadable:
+        GTFS(input_object=test_gtfs_file)
+    assert unreadable.value.file_name == "shapes.txt"
+
+    # Other entries are skipped with a warning
+    notes_file: pathlib.Path = tmp_path.joinpath("with_notes.zip")
+    with zipfile.ZipFile(notes_file, "w") as zip_ref:
+        for file in gtfs_test_folder.glob("*.txt"):
+            zip_ref.write(file, arcname=file.name)
+        zip_ref.writestr("notes.md", "Exported by hand")
+    with zipfile.ZipFile(notes_file, "r") as zip_ref:
+        header_offset = zip_ref.getinfo("notes.md").header_offset
+    with open(notes_file, "r+b") as fp:
+        fp.seek(header_offset)
+        fp.write(b"XXXX")
+    with GTFS(input_object=notes_file) as gtfs:
+        extracted_files: List[str] = sorted(file.name for file in gtfs._feed_folder.iterdir())
+    assert extracted_files == sorted(file.name for file in gtfs_test_folder.glob("*.txt"))
+    with pytest.raises(WarningAsError) as error:
+        GTFS(input_object=notes_file, fail_on_warning=True)
+    assert "notes.md" in error.value.warning
+
+
+def test_table_batches(gtfs_test_folder: pathlib.Path) -> None:
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        batch_sizes: List[int] = [len(batch) for batch in gtfs.table_batches("stop_times.txt", 100)]
+
+    assert sum(batch_sizes) == 28701
+    assert all(batch_size <= 100 for batch_size in batch_sizes)
+    # The final partial batch is emitted as well.
+    assert batch_sizes[-1] == 1
+
+
+def test_read_table_tab_delimited(gtfs_test_folder: pathlib.Path) -> None:
+    agency_file: pathlib.Path = gtfs_test_folder.joinpath("agency.txt")
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        expected: pd.DataFrame = gtfs.read_table("agency.txt")
+    agencies: pd.DataFrame = pd.read_csv(agency_file, dtype=str)
+    agencies.to_csv(agency_file, sep="\t", index=False, encoding="utf-8-sig")
+
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        agency: pd.DataFrame = gtfs.read_table("agency.txt")
+    pd.testing.assert_frame_equal(agency, expected)
+    # The input folder is left as it is
+    assert "\t" in agency_file.read_text(encoding="utf-8-sig").splitlines()[0]
+
+
+def test_check_line_endings(gtfs_test_folder: pathlib.Path) -> None:
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        assert gtfs.check_line_endings() == []
+
+    agency_file: pathlib.Path = gtfs_test_folder.joinpath("agency.txt")
+    agency_file.write_bytes(agency_file.read_bytes().replace(b"\n", b"\r\n"))
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        assert gtfs.check_line_endings() == ["agency.txt contains \\r\\n line endings"]
+
+
+def test_tar_gz_input(gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path) -> None:
+    test_gtfs_file: pathlib.Path = tmp_path.joinpath("feed.tar.gz")
+    with tarfile.open(test_gtfs_file, "w:gz") as tar_ref:
+        for file in gtfs_test_folder.glob("*.txt"):
+            tar_ref.add(file, arcname=file.name)
+
+    with GTFS(input_object=test_gtfs_file) as gtfs:
+        assert sorted(gtfs.get_filenames()) == sorted(file.name for file in gtfs_test_folder.glob("*.txt"))
+        stops: pd.DataFrame = gtfs.read_table("stops.txt")
+    assert len(stops) == 1449
+    assert stops["stop_lat"].dtype == np.float64
+
+
+def test_nested_folder_input(gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path) -> None:
+    parent_folder: pathlib.Path = tmp_path.joinpath("download")
+    feed_folder: pathlib.Path = parent_folder.joinpath("ic_ice_gtfs_germany")
+    shutil.copytree(gtfs_test_folder, feed_folder)
+    parent_folder.joinpath("notes").mkdir()
+
+    with GTFS(input_object=parent_folder) as gtfs:
+        assert gtfs._feed_folder == feed_folder
+        assert sorted(gtfs.get_filenames()) == sorted(file.name for file in gtfs_test_folder.glob("*.txt"))
+        assert len(gtfs.read_table("stops.txt")) == 1449
+
+    with Validator(input_object=parent_folder) as validator:
+        assert validator.validate().findings == []
+
+    # Two feeds side by side are ambiguous, so neither is picked
+    shutil.copytree(gtfs_test_folder, parent_folder.joinpath("copy"))
+    with pytest.raises(GtfsIncompleteException):
+        GTFS(input_object=parent_folder)
+
+
+def test_unsupported_input_file(tmp_path: pathlib.Path) -> None:
+    test_gtfs_file: pathlib.Path = tmp_path.joinpath("feed.zip")
+    test_gtfs_file.write_text("no archive")
+    with pytest.raises(UnsupportedInputFile):
+        GTFS(input_object=test_gtfs_file)
+
+
+def test_content_fingerprint(gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path) -> None:
+    stored_file: pathlib.Path = tmp_path.joinpath("stored.zip")
+    deflated_file: pathlib.Path = tmp_path.joinpath("deflated.zip")
+    for test_gtfs_file, compression in ((stored_file, zipfile.ZIP_STORED), (deflated_file, zipfile.ZIP_DEFLATED)):
+        with zipfile.ZipFile(test_gtfs_file, "w", compression=compression) as zip_ref:
+            for file in sorted(gtfs_test_folder.glob("*.txt"), reverse=compression == zipfile.ZIP_STORED):
+                zip_ref.write(file, arcname=file.name)
+
+    fingerprints: List[str] = []
+    for input_object in (gtfs_test_folder, stored_file, deflated_file):
+        with GTFS(input_object=input_object) as gtfs:
+            fingerprints.append(gtfs.content_fingerprint())
+    assert len(set(fingerprints)) == 1
+    assert len(fingerprints[0]) == 64
+
+    agency_file: pathlib.Path = gtfs_test_folder.joinpath("agency.txt")
+    agency_file.write_text(agency_file.read_text() + "\n")
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        assert gtfs.content_fingerprint() != fingerprints[0]
+
+
+def test_route_bounds(gtfs_test_folder: pathlib.Path) -> None:
+    # Route 9 is the ICE 79 from Bruxelles Midi over Köln Hbf to Frankfurt(Main)Hbf
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    stops: pd.DataFrame = pd.read_csv(stops_file, dtype={"stop_id": str})
+    corridor: pd.DataFrame = stops[stops["stop_name"].isin(["Bruxelles Midi", "Köln Hbf", "Frankfurt(Main)Hbf"])]
+    # A placeholder coordinate on one of the stops of the route must not stretch the bounds
+    stops.loc[stops["stop_name"] == "Montabaur", ["stop_lat", "stop_lon"]] = [0.0, 0.0]
+    stops.to_csv(stops_file, index=False)
+
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        bounds: pd.DataFrame = gtfs.route_bounds().set_index("route_id")
+
+    route_bounds: pd.Series = bounds.loc["9"]
+    assert route_bounds["min_lat"] <= corridor["stop_lat"].min()
+    assert route_bounds["max_lat"] >= corridor["stop_lat"].max()
+    assert route_bounds["min_lon"] <= corridor["stop_lon"].min()
+    assert route_bounds["max_lon"] >= corridor["stop_lon"].max()
+    assert route_bounds["min_lat"] > 0
+    assert route_bounds["min_lon"] > 0
+
+
+def test_route_endpoints(gtfs_test_folder: pathlib.Path) -> None:
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        endpoints: pd.DataFrame = gtfs.route_endpoints()
+    assert endpoints[["route_id", "direction_id"]].value_counts().max() == 1
+    # The trips of route 9, the ICE 79, run between Brussels, Cologne, Frankfurt and Arnhem in several patterns
+    route: pd.DataFrame = endpoints[endpoints["route_id"] == "9"]
+    assert route[["first_stop", "last_stop", "trips"]].values.tolist() == [
+        ["Bruxelles Midi", "Frankfurt(Main)Hbf", 19]
+    ]
+
+
+def test_find_clustered_stops(gtfs_test_folder: pathlib.Path) -> None:
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    stops: pd.DataFrame = pd.read_csv(stops_file, dtype=str)
+    # Two stops 5 meters apart along a meridian, far from the other stops of the feed
+    north: str = str(50.0 + np.degrees(5.0 / EARTH_RADIUS_METERS))
+    duplicates: pd.DataFrame = pd.DataFrame(
+        [
+            {"stop_name": "Duplicate", "stop_id": "cluster_a", "stop_lat": "50.0", "stop_lon": "10.0"},
+            {"stop_name": "Duplicate", "stop_id": "cluster_b", "stop_lat": north, "stop_lon": "10.0"},
+        ]
+    )
+    pd.concat([stops, duplicates]).to_csv(stops_file, index=False)
+
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        assert ["cluster_a", "cluster_b"] in gtfs.find_clustered_stops(10.0)
+        clusters: List[List[str]] = gtfs.find_clustered_stops(1.0)
+    assert not any("cluster_a" in cluster or "cluster_b" in cluster for cluster in clusters)
+    # Stops sharing the exact coordinates of Aachen Hbf are clustered at any distance
+    assert any({"318", "385", "719"} <= set(cluster) for cluster in clusters)
+
+
+def test_find_duplicate_trips(gtfs_test_folder: pathlib.Path) -> None:
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        assert gtfs.find_duplicate_trips() == []
+
+    trips_file: pathlib.Path = gtfs_test_folder.joinpath("trips.txt")
+    trips: pd.DataFrame = pd.read_csv(trips_file, dtype=str)
+    copies: pd.DataFrame = trips[trips["trip_id"] == "1483"]
+    pd.concat([trips, copies.assign(trip_id="copy"), copies.assign(trip_id="shifted")]).to_csv(trips_file, index=False)
+    stop_times_file: pathlib.Path = gtfs_test_folder.joinpath("stop_times.txt")
+    stop_times: pd.DataFrame = pd.read_csv(stop_times_file, dtype=str)
+    trip_stop_times: pd.DataFrame = stop_times[stop_times["trip_id"] == "1483"]
+    # The copy is listed in reverse order, the shifted trip departs a minute later at its first stop
+    shifted: pd.DataFrame = trip_stop_times.assign(trip_id="shifted")
+    shifted.iloc[0, shifted.columns.get_loc("departure_time")] = "19:23:00"
+    pd.concat([stop_times, trip_stop_times.assign(trip_id="copy").iloc[::-1], shifted]).to_csv(
+        stop_times_file, index=False
+    )
+
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        assert gtfs.find_duplicate_trips() == [["1483", "copy"]]
+
+
+def test_service_calendar(gtfs_test_folder: pathlib.Path) -> None:
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        service_calendar: Dict[str, List[date]] = gtfs.service_calendar()
+    # Service 68 runs on Sundays from 2022-10-02 to 2022-10-09
+    assert service_calendar["68"] == [date(2022, 10, 2), date(2022, 10, 9)]
+    # Service 77 is only added by calendar_dates.txt
+    assert service_calendar["77"] == [date(2022, 10, 5)]
+
+    calendar_file: pathlib.Path = gtfs_test_folder.joinpath("calendar.txt")
+    calendar: pd.DataFrame = pd.read_csv(calendar_file, dtype=str)
+    calendar.loc[calendar["service_id"] == "68", "end_date"] = "99991231"
+    calendar.to_csv(calendar_file, index=False)
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        service_calendar = gtfs.service_calendar()
+        # Open-ended services are only expanded for MAX_SERVICE_DAYS days
+        assert service_calendar["68"][:2] == [date(2022, 10, 2), date(2022, 10, 9)]
+        assert service_calendar["68"][-1] < date(2022, 10, 2) + timedelta(days=MAX_SERVICE_DAYS)
+        # A single day is looked up without expanding the range
+        assert "68" in gtfs._active_service_ids(date(2099, 12, 27))
+        assert "68" not in gtfs._active_service_ids(date(2099, 12, 28))
+
+
+def test_weekday_coverage(gtfs_test_folder: pathlib.Path) -> None:
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        coverage: List[int] = gtfs.weekday_coverage()
+        service_days: int = sum(len(dates) for dates in gtfs._service_dates().values())
+
+    assert len(coverage) == 7
+    assert sum(coverage) == service_days
+    # The feed runs from Sunday 20221002 to Sunday 20221009 and has weekend service
+    assert coverage[5] > 0
+    assert coverage[6] > 0
+
+
+def test_open_incomplete_feed_unchecked(gtfs_test_folder: pathlib.Path) -> None:
+    gtfs_test_folder.joinpath("routes.txt").unlink()
+    with pytest.raises(GtfsIncompleteException):
+        GTFS(input_object=gtfs_test_folder)
+
+    with GTFS(input_object=gtfs_test_folder, check_required_files=False) as gtfs:
+        stops: pd.DataFrame = gtfs.read_table("stops.txt")
+        with pytest.raises(GtfsFileNotFound):
+            gtfs.read_table("routes.txt")
+    assert len(stops) == 1449
+
+
+def test_trip_distances(gtfs_test_folder: pathlib.Path) -> None:
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        trip_distances: pd.DataFrame = gtfs.trip_distances().set_index("trip_id")
+    distances: pd.Series = trip_distances["distance_meters"]
+
+    # Trip 1003 runs from Berlin Ostbahnhof to Stuttgart Hbf, about 511 km apart, and has no shape
+    assert 511000 < distances["1003"] < 1000000
+    assert pd.isna(trip_distances.loc["1003", "shape_dist_traveled"])
+    # Shape 10001 of trip 2175 ends with a shape_dist_traveled of 89.914 in the units of the feed. All its points
+    # share one coordinate, so the distance in meters is taken from the stops.
+    assert trip_distances.loc["2175", "shape_dist_traveled"] == pytest.approx(89.914)
+    assert distances["2175"] > 1000
+    # Shape 10002 of trip 1364 is missing from shapes.txt, so its stops are used
+    assert distances["1364"] > 0
+    assert (distances > 0).all()
+
+
+def test_trip_durations(gtfs_strict_test_folder: pathlib.Path) -> None:
+    with GTFS(input_object=gtfs_strict_test_folder) as gtfs:
+        durations: pd.DataFrame = gtfs.trip_durations().set_index("route_id")
+    assert durations.loc["52", "trips"] == 18
+    assert durations.loc["52", "mean_duration"] > 0
+    # Trip 1003 of route 52 runs from 09:49:00 to 18:46:00
+    assert durations.loc["52", "max_duration"] >= 32220
+    assert (durations["min_duration"] <= durations["mean_duration"]).all()
+
+    stop_times_file: pathlib.Path = gtfs_strict_test_folder.joinpath("stop_times.txt")
+    stop_times: pd.DataFrame = pd.read_csv(stop_times_file, dtype=str)
+    stop_times.loc[(stop_times["trip_id"] == "1483") & (stop_times["stop_sequence"] == "0"), "arrival_time"] = None
+    stop_times.loc[(stop_times["trip_id"] == "1483") & (stop_times["stop_sequence"] == "0"), "departure_time"] = None
+    stop_times.to_csv(stop_times_file, index=False)
+    with GTFS(input_object=gtfs_strict_test_folder) as gtfs:
+        # Trip 1483 of route 100 has no time at its first stop, only trip 1775 is left
+        assert gtfs.trip_durations().set_index("route_id").loc["100", "trips"] == 1
+    with GTFS(input_object=gtfs_strict_test_folder, fail_on_warning=True) as gtfs:
+        with pytest.raises(WarningAsError):
+            gtfs.trip_durations()
+
+
+def test_check_trip_shape_alignment(gtfs_test_folder: pathlib.Path) -> None:
+    stop_times: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("stop_times.txt"), dtype=str)
+    stops: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("stops.txt"), dtype={"stop_id": str})
+    # Trip 1003 gets a shape through its stops, trip 1483 one that runs about 11 km north of its stops
+    shape_points: pd.DataFrame = stop_times[stop_times["trip_id"].isin(["1003", "1483"])].merge(stops, on="stop_id")
+    shape_points = pd.DataFrame(
+        {
+            "shape_id": "shape_" + shape_points["trip_id"],
+            "shape_pt_sequence": shape_points["stop_sequence"],
+            "shape_pt_lat": shape_points["stop_lat"] + (shape_points["trip_id"] == "1483") * 0.1,
+            "shape_pt_lon": shape_points["stop_lon"],
+        }
+    )
+    shapes_file: pathlib.Path = gtfs_test_folder.joinpath("shapes.txt")
+    pd.concat([pd.read_csv(shapes_file, dtype=str), shape_points]).to_csv(shapes_file, index=False)
+    trips_file: pathlib.Path = gtfs_test_folder.joinpath("trips.txt")
+    trips: pd.DataFrame = pd.read_csv(trips_file, dtype=str)
+    trips.loc[trips["trip_id"].isin(["1003", "1483"]), "shape_id"] = "shape_" + trips["trip_id"]
+    trips.to_csv(trips_file, index=False)
+
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        # Shape 10001 of trips 1379 and 2175 is a stub in Heidelberg, far from their stops
+        assert gtfs.check_trip_shape_alignment(1000) == ["1379", "1483", "2175"]
+        assert gtfs.check_trip_shape_alignment(1000000) == []
+
+
+def test_headways(gtfs_test_folder: pathlib.Path) -> None:
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        headways: pd.DataFrame = gtfs.headways(date(2022, 10, 5)).set_index(["route_id", "direction_id", "stop_id"])
+
+    assert (headways["min_headway"] > 0).all()
+    assert (headways["min_headway"] <= headways["median_headway"]).all()
+    assert (headways["median_headway"] <= headways["max_headway"]).all()
+    # Route 38 leaves stop 956 every hour
+    assert headways.loc[("38", 0, "956")].tolist() == [3600, 3600, 3600]
+    assert headways.loc[("50", 0, "948")].tolist() == [2760, 3720, 7020]
+
+
+def test_busiest_stops(gtfs_test_folder: pathlib.Path) -> None:
+    with GTFS(input_object=gtfs_test_folder) as gtfs:
+        busiest: pd.DataFrame = gtfs.busiest_stops(3)
+        busiest_on_day: pd.DataFrame = gtfs.busiest_stops(2, date(2022, 10, 4))
+    assert busiest.values.tolist() == [
+        ["24", "Kassel-Wilhelmshöhe", 190],
+        ["206", "Göttingen", 184],
+        ["29", "Göttingen", 183],
+    ]
+    # On a Tuesday Hamburg Dammtor is busier than Kassel-Wilhelmshöhe
+    assert busiest_on_day.values.tolist() == [["1418", "Hamburg Dammtor", 74], ["738", "Hamburg Dammtor", 71]]
+
+
+def test_time_to_seconds() -> None:
+    times: pd.Series = pd.Series(["5:00:00", "23:59:30", "25:10:00", None])
+    assert time_to_seconds(times).tolist()[:3] == [18000, 86370, 90600]
+    assert pd.isna(time_to_seconds(times).iloc[3])
+
+
+def test_extract_files_from_archive(gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path) -> None:
+    test_gtfs_file: pathlib.Path = tmp_path.joinpath("feed.zip")
+    with zipfile.ZipFile(test_gtfs_file, "w") as zip_ref:
+        for file in gtfs_test_folder.glob("*.txt"):
+            zip_ref.write(file, arcname=file.name)
+
+    with GTFS(input_object=test_gtfs_file, extract_files=("calendar.txt",)) as gtfs:
+        assert [file.name for file in gtfs._feed_folder.iterdir()] == ["calendar.txt"]
+        assert gtfs.service_date_range() == ("2022-10-02 00:00:00", "2022-10-09 00:00:00")
+
+    # The required files are still checked against the whole archive
+    with zipfile.ZipFile(test_gtfs_file, "w") as zip_ref:
+        zip_ref.write(gtfs_test_folder.joinpath("calendar.txt"), arcname="calendar.txt")
+    with pytest.raises(GtfsIncompleteException):
+        GTFS(input_object=test_gtfs_file, extract_files=("calendar.txt",))
+
+
+def test_invalid_zip_is_not_retried(tmp_path: pathlib.Path, monkeypatch: pytest.MonkeyPatch) -> None:
+    test_gtfs_file: pathlib.Path = tmp_path.joinpath("feed.zip")
+    test_gtfs_file.write_bytes(b"PK\x03\x04 truncated archive")
+    attempts: List[pathlib.Path] = []
+    extract_archive = GTFS._extract_archive
+
+    def record_extract_archive(self: GTFS, input_file: pathlib.Path, extract_path: pathlib.Path) -> None:
+        attempts.append(input_file)
+        extract_archive(self, input_file, extract_path)
+
+    monkeypatch.setattr(GTFS, "_extract_archive", record_extract_archive)
+    monkeypatch.setattr(gtfs_module.time, "sleep", lambda seconds: None)
+    with pytest.raises(zipfile.BadZipFile):
+        GTFS(input_object=test_gtfs_file, io_retries=3)
+    assert len(attempts) == 1
+
+
+def test_transient_read_error_is_retried(
+    gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path, monkeypatch: pytest.MonkeyPatch
+) -> None:
+    test_gtfs_file: pathlib.Path = tmp_path.joinpath("feed.zip")
+    with zipfile.ZipFile(test_gtfs_file, "w") as zip_ref:
+        for file in gtfs_test_folder.glob("*.txt"):
+            zip_ref.write(file, arcname=file.name)
+    attempts: List[pathlib.Path] = []
+    extract_archive = GTFS._extract_archive
+
+    def fail_first_extract_archive(self: GTFS, input_file: pathlib.Path, extract_path: pathlib.Path) -> None:
+        attempts.append(input_file)
+        if len(attempts) == 1:
+            raise OSError("Stale file handle")
+        extract_archive(self, input_file, extract_path)
+
+    delays: List[float] = []
+    monkeypatch.setattr(GTFS, "_extract_archive", fail_first_extract_archive)
+    monkeypatch.setattr(gtfs_module.time, "sleep", delays.append)
+    with GTFS(input_object=test_gtfs_file, io_retries=2) as gtfs:
+        assert gtfs.feed_summary()["agencies"] == 13
+    assert len(attempts) == 2
+    assert delays == [0.1]
+
+    attempts.clear()
+    with pytest.raises(OSError):
+        GTFS(input_object=test_gtfs_file)
+    assert len(attempts) == 1
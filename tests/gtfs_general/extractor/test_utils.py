@@ -0,0 +1,31 @@
+import pathlib
+from typing import Set
+
+import pandas as pd
+
+from gtfs_general.extractor.utils import MembershipStrategy, filter_rows_by_ids, format_bytes
+
+
+def test_filter_rows_by_ids_strategies_match(gtfs_test_folder: pathlib.Path) -> None:
+    stop_times: pd.DataFrame = pd.read_csv(gtfs_test_folder.joinpath("stop_times.txt"), dtype=str)
+    trip_ids: Set[str] = {"1483", "2369", "1207", "unknown"}
+
+    isin_rows: pd.DataFrame = filter_rows_by_ids(stop_times, trip_ids, ["trip_id"], strategy=MembershipStrategy.isin)
+    join_rows: pd.DataFrame = filter_rows_by_ids(stop_times, trip_ids, ["trip_id"], strategy=MembershipStrategy.join)
+
+    assert len(isin_rows) > 0
+    pd.testing.assert_frame_equal(isin_rows.reset_index(drop=True), join_rows.reset_index(drop=True))
+
+
+def test_filter_rows_by_ids_multiple_columns() -> None:
+    transfers: pd.DataFrame = pd.DataFrame({"from_stop_id": ["1", "1", "2"], "to_stop_id": ["2", "3", "1"]})
+
+    for strategy in MembershipStrategy:
+        rows: pd.DataFrame = filter_rows_by_ids(transfers, {"1", "2"}, ["from_stop_id", "to_stop_id"], strategy)
+        assert rows.values.tolist() == [["1", "2"], ["2", "1"]]
+
+
+def test_format_bytes() -> None:
+    assert format_bytes(512) == "512 B"
+    assert format_bytes(1536) == "1.5 KiB"
+    assert format_bytes(3 * 1024**3) == "3.0 GiB"
@@ -0,0 +1,365 @@
+import json
+import pathlib
+from typing import Dict, List
+
+import pandas as pd
+from typer.testing import CliRunner
+
+from gtfs_general import VALIDATION_ERROR, main
+from gtfs_general.extractor.validator import Validator
+
+runner = CliRunner()
+
+
+def test_validate_valid_feed(gtfs_test_folder: pathlib.Path) -> None:
+    result = runner.invoke(main.app, ["--logging", "INFO", "validate", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == 0
+    assert "Found 0 issues" in result.stdout
+
+
+def test_check_timezones_platform_overrides_station(gtfs_test_folder: pathlib.Path) -> None:
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    stops: pd.DataFrame = pd.read_csv(stops_file, dtype=str)
+    stops["parent_station"] = None
+    stops["stop_timezone"] = None
+    stops.loc[stops["stop_id"] == "318", ["parent_station", "stop_timezone"]] = ["station_1", "Europe/Vienna"]
+    stops.loc[stops["stop_id"] == "385", "parent_station"] = "station_1"
+    station: pd.DataFrame = pd.DataFrame(
+        [
+            {
+                "stop_name": "Aachen Hbf",
+                "stop_id": "station_1",
+                "stop_lat": "50.7678",
+                "stop_lon": "6.091499",
+                "stop_timezone": "Europe/Berlin",
+            }
+        ]
+    )
+    pd.concat([stops, station]).to_csv(stops_file, index=False)
+
+    with Validator(input_object=gtfs_test_folder) as validator:
+        findings: List[str] = validator.check_timezones()
+    assert len(findings) == 1
+    assert "stop 318" in findings[0]
+    assert "conflicts" in findings[0]
+
+    # Timezone conflicts are warnings, they are reported without failing the validation
+    result = runner.invoke(main.app, ["--logging", "INFO", "validate", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == 0
+    assert "Found 1 issues" in result.stdout
+
+
+def test_check_stop_sequences_repeated_value(gtfs_test_folder: pathlib.Path) -> None:
+    with Validator(input_object=gtfs_test_folder) as validator:
+        assert validator.check_stop_sequences() == []
+
+    stop_times_file: pathlib.Path = gtfs_test_folder.joinpath("stop_times.txt")
+    stop_times: pd.DataFrame = pd.read_csv(stop_times_file, dtype=str)
+    trip_rows: pd.Index = stop_times[stop_times["trip_id"] == "1483"].index
+    stop_times.loc[trip_rows[1], "stop_sequence"] = stop_times.loc[trip_rows[0], "stop_sequence"]
+    stop_times.to_csv(stop_times_file, index=False)
+
+    with Validator(input_object=gtfs_test_folder) as validator:
+        findings: List[str] = validator.check_stop_sequences()
+    assert len(findings) == 1
+    assert "trip 1483 repeats stop_sequence" in findings[0]
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "validate", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == VALIDATION_ERROR
+
+
+def test_check_roundtrip(gtfs_test_folder: pathlib.Path) -> None:
+    with Validator(input_object=gtfs_test_folder) as validator:
+        assert validator.check_roundtrip() == []
+
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "--no-progress",
+            "validate",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--self-check",
+        ],
+    )
+    assert result.exit_code == 0
+
+
+def test_check_fares_dangling_fare_id(gtfs_test_folder: pathlib.Path) -> None:
+    pd.DataFrame(
+        [{"fare_id": "regular", "price": "9.90", "currency_type": "EUR", "payment_method": "0", "transfers": "0"}]
+    ).to_csv(gtfs_test_folder.joinpath("fare_attributes.txt"), index=False)
+    pd.DataFrame([{"fare_id": "regular", "route_id": "9"}, {"fare_id": "missing_fare", "route_id": "9"}]).to_csv(
+        gtfs_test_folder.joinpath("fare_rules.txt"), index=False
+    )
+
+    with Validator(input_object=gtfs_test_folder) as validator:
+        findings: List[str] = validator.check_fares()
+    assert findings == ["fare_rules.txt: fare_id missing_fare is missing from fare_attributes.txt"]
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "validate", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == VALIDATION_ERROR
+
+
+def test_check_calendar_ranges(gtfs_test_folder: pathlib.Path) -> None:
+    with Validator(input_object=gtfs_test_folder) as validator:
+        assert validator.check_calendar_ranges() == []
+
+    calendar_file: pathlib.Path = gtfs_test_folder.joinpath("calendar.txt")
+    calendar: pd.DataFrame = pd.read_csv(calendar_file, dtype=str)
+    calendar.loc[calendar["service_id"] == "68", ["start_date", "end_date"]] = ["20221009", "20221002"]
+    calendar.to_csv(calendar_file, index=False)
+
+    with Validator(input_object=gtfs_test_folder) as validator:
+        findings: List[str] = validator.check_calendar_ranges()
+    assert findings == ["calendar.txt: service_id 68 has start_date 20221009 after end_date 20221002"]
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "validate", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == VALIDATION_ERROR
+    assert "error: calendar.txt: service_id 68 has start_date 20221009 after end_date 20221002" in result.stdout
+
+
+def test_check_dead_services(gtfs_test_folder: pathlib.Path) -> None:
+    with Validator(input_object=gtfs_test_folder) as validator:
+        assert validator.check_dead_services() == []
+
+    calendar_file: pathlib.Path = gtfs_test_folder.joinpath("calendar.txt")
+    calendar: pd.DataFrame = pd.read_csv(calendar_file, dtype=str)
+    dead_service: pd.DataFrame = calendar.head(1).assign(service_id="never")
+    dead_service[["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"]] = "0"
+    pd.concat([calendar, dead_service]).to_csv(calendar_file, index=False)
+    trips_file: pathlib.Path = gtfs_test_folder.joinpath("trips.txt")
+    trips: pd.DataFrame = pd.read_csv(trips_file, dtype=str)
+    trips.loc[trips["trip_id"] == "1483", "service_id"] = "never"
+    trips.to_csv(trips_file, index=False)
+
+    with Validator(input_object=gtfs_test_folder) as validator:
+        findings: List[str] = validator.check_dead_services()
+    assert findings == ["trips.txt: 1 trips use service_id never which is never active, e.g. trip 1483"]
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "validate", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == 0
+    assert "warning: trips.txt: 1 trips use service_id never" in result.stdout
+
+
+def test_check_languages(gtfs_test_folder: pathlib.Path) -> None:
+    with Validator(input_object=gtfs_test_folder) as validator:
+        assert validator.check_languages() == []
+
+    agency_file: pathlib.Path = gtfs_test_folder.joinpath("agency.txt")
+    agency: pd.DataFrame = pd.read_csv(agency_file, dtype=str)
+    agency.loc[agency["agency_id"] == "1", "agency_lang"] = "germn"
+    agency.loc[agency["agency_id"] == "10", "agency_lang"] = None
+    agency.to_csv(agency_file, index=False)
+    pd.DataFrame(
+        [
+            {"table_name": "stops", "field_name": "stop_name", "language": "en-US", "translation": "Main Station"},
+            {"table_name": "stops", "field_name": "stop_name", "language": "de_DE", "translation": "Hauptbahnhof"},
+        ]
+    ).to_csv(gtfs_test_folder.joinpath("translations.txt"), index=False)
+
+    with Validator(input_object=gtfs_test_folder) as validator:
+        findings: List[str] = validator.check_languages()
+    assert findings == [
+        "agency.txt: row 1 has agency_lang 'germn' which isn't a well-formed BCP-47 tag",
+        "translations.txt: row 2 has language 'de_DE' which isn't a well-formed BCP-47 tag",
+    ]
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "validate", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == VALIDATION_ERROR
+
+
+def test_check_colors(gtfs_test_folder: pathlib.Path) -> None:
+    with Validator(input_object=gtfs_test_folder) as validator:
+        assert validator.check_colors() == []
+
+    routes_file: pathlib.Path = gtfs_test_folder.joinpath("routes.txt")
+    routes: pd.DataFrame = pd.read_csv(routes_file, dtype=str)
+    routes["route_color"] = None
+    routes["route_text_color"] = "ffffff"
+    routes.loc[0, "route_color"] = "#FF0000"
+    routes.loc[1, "route_color"] = "FF0000"
+    routes.loc[2, "route_text_color"] = "white"
+    routes.to_csv(routes_file, index=False)
+    # Colors of all digits in a column without a type in the schema
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    stops: pd.DataFrame = pd.read_csv(stops_file, dtype=str)
+    stops["stop_color"] = "000000"
+    stops.loc[1, "stop_color"] = "00000"
+    stops.to_csv(stops_file, index=False)
+
+    with Validator(input_object=gtfs_test_folder) as validator:
+        findings: List[str] = validator.check_colors()
+    assert findings == [
+        "routes.txt: row 1 has route_color '#FF0000' which isn't a hex color",
+        "routes.txt: row 3 has route_text_color 'white' which isn't a hex color",
+        "stops.txt: row 2 has stop_color '00000' which isn't a hex color",
+    ]
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "validate", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == VALIDATION_ERROR
+
+
+def test_check_transfers(gtfs_test_folder: pathlib.Path) -> None:
+    with Validator(input_object=gtfs_test_folder) as validator:
+        assert validator.check_transfers() == []
+
+    pd.DataFrame(
+        [
+            {"from_stop_id": "916", "to_stop_id": "1014", "transfer_type": "2", "min_transfer_time": None},
+            {"from_stop_id": "1014", "to_stop_id": "916", "transfer_type": "0", "min_transfer_time": "120"},
+            {"from_stop_id": "318", "to_stop_id": "385", "transfer_type": "2", "min_transfer_time": "180"},
+            {"from_stop_id": "385", "to_stop_id": "318", "transfer_type": None, "min_transfer_time": None},
+            {"from_stop_id": "385", "to_stop_id": "719", "transfer_type": "5", "min_transfer_time": None},
+        ]
+    ).to_csv(gtfs_test_folder.joinpath("transfers.txt"), index=False)
+
+    with Validator(input_object=gtfs_test_folder) as validator:
+        findings: List[str] = validator.check_transfers()
+    assert findings == [
+        "transfers.txt: row 1 has transfer_type 2 without a min_transfer_time",
+        "transfers.txt: row 2 sets min_transfer_time 120 although its transfer_type is 0 instead of 2",
+        "transfers.txt: row 5 has transfer_type 5 which isn't 0 to 3",
+    ]
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "validate", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == VALIDATION_ERROR
+
+
+def test_check_coordinates(gtfs_test_folder: pathlib.Path) -> None:
+    with Validator(input_object=gtfs_test_folder) as validator:
+        assert validator.check_coordinates() == []
+
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    stops: pd.DataFrame = pd.read_csv(stops_file, dtype=str)
+    # UTM zone 32N northing and easting of Aachen Hbf in meters
+    stops.loc[stops["stop_id"].isin(["318", "385"]), ["stop_lat", "stop_lon"]] = ["5624925.5", "294510.25"]
+    stops.to_csv(stops_file, index=False)
+
+    with Validator(input_object=gtfs_test_folder) as validator:
+        findings: List[str] = validator.check_coordinates()
+    assert findings == [
+        "stops.txt: 2 of 1449 stops have coordinates outside the WGS84 range, e.g. stop 318 at 5624925.5, 294510.25. "
+        "The coordinates may be projected, e.g. in UTM meters"
+    ]
+
+    result = runner.invoke(main.app, ["--logging", "INFO", "validate", "--input-object", gtfs_test_folder.__str__()])
+    assert result.exit_code == 0
+    assert "warning: stops.txt: 2 of 1449 stops have coordinates outside the WGS84 range" in result.stdout
+
+
+def test_check_stop_names(gtfs_test_folder: pathlib.Path) -> None:
+    with Validator(input_object=gtfs_test_folder) as validator:
+        assert validator.check_stop_names() == []
+
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    stops: pd.DataFrame = pd.read_csv(stops_file, dtype=str)
+    # Two platforms of the same station, only one of them is named, and an unnamed generic node
+    stops["location_type"] = None
+    stops.loc[stops["stop_id"].isin(["318", "385"]), "location_type"] = "0"
+    stops.loc[stops["stop_id"] == "385", "stop_name"] = None
+    stops.loc[stops["stop_id"] == "916", ["stop_name", "location_type"]] = [None, "3"]
+    stops.to_csv(stops_file, index=False)
+
+    with Validator(input_object=gtfs_test_folder) as validator:
+        assert validator.check_stop_names() == ["stops.txt: stop 385 of location_type 0 has no stop_name"]
+
+
+def test_validate_severity(gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path) -> None:
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    stops: pd.DataFrame = pd.read_csv(stops_file, dtype=str)
+    stops.loc[stops["stop_id"] == "318", ["stop_lat", "stop_lon"]] = ["5624925.5", "294510.25"]
+    stops.to_csv(stops_file, index=False)
+    report_file: pathlib.Path = tmp_path.joinpath("report.json")
+
+    def validate(*severity: str) -> int:
+        result = runner.invoke(
+            main.app,
+            [
+                "--logging",
+                "INFO",
+                "validate",
+                "--input-object",
+                gtfs_test_folder.__str__(),
+                "--report",
+                report_file.__str__(),
+                *(option for value in severity for option in ("--severity", value)),
+            ],
+        )
+        return result.exit_code
+
+    # The coordinates are a warning by default, they are reported but don't fail the validation
+    assert validate() == 0
+    with open(report_file, "r") as fp:
+        assert json.load(fp)["warnings"] == 1
+    assert validate("coordinates=error") == VALIDATION_ERROR
+    with open(report_file, "r") as fp:
+        report: Dict = json.load(fp)
+    assert (report["errors"], report["warnings"]) == (1, 0)
+    assert report["findings"][0]["check"] == "coordinates"
+
+    assert validate("coordinates=ignore") == 0
+    with open(report_file, "r") as fp:
+        assert json.load(fp)["issues"] == 0
+    # warn is short for warning and blanks around the check and the severity don't matter
+    assert validate(" coordinates = warn ") == 0
+    with open(report_file, "r") as fp:
+        report = json.load(fp)
+    assert (report["errors"], report["warnings"]) == (0, 1)
+    assert validate("coordinate=error") == 2
+    assert validate("coordinates=fatal") == 2
+
+    # Unknown checks are rejected before the feed is read
+    result = runner.invoke(
+        main.app,
+        ["validate", "--input-object", tmp_path.joinpath("missing").__str__(), "--severity", "coordinate=error"],
+    )
+    assert result.exit_code == 2
+    assert "Unknown check" in result.output
+
+
+def test_validate_report(gtfs_test_folder: pathlib.Path, tmp_path: pathlib.Path) -> None:
+    stops_file: pathlib.Path = gtfs_test_folder.joinpath("stops.txt")
+    stops: pd.DataFrame = pd.read_csv(stops_file, dtype=str)
+    stops["parent_station"] = None
+    stops["stop_timezone"] = None
+    stops.loc[stops["stop_id"] == "318", ["parent_station", "stop_timezone"]] = ["385", "Europe/Vienna"]
+    stops.loc[stops["stop_id"] == "385", "stop_timezone"] = "Europe/Berlin"
+    stops.to_csv(stops_file, index=False)
+    stop_times_file: pathlib.Path = gtfs_test_folder.joinpath("stop_times.txt")
+    stop_times: pd.DataFrame = pd.read_csv(stop_times_file, dtype=str)
+    trip_rows: pd.Index = stop_times[stop_times["trip_id"] == "1483"].index
+    stop_times.loc[trip_rows[1], "stop_sequence"] = stop_times.loc[trip_rows[0], "stop_sequence"]
+    stop_times.to_csv(stop_times_file, index=False)
+    agency_file: pathlib.Path = gtfs_test_folder.joinpath("agency.txt")
+    agency: pd.DataFrame = pd.read_csv(agency_file, dtype=str)
+    agency.loc[agency["agency_id"] == "1", "agency_lang"] = "germn"
+    agency.to_csv(agency_file, index=False)
+
+    report_file: pathlib.Path = tmp_path.joinpath("report.json")
+    result = runner.invoke(
+        main.app,
+        [
+            "--logging",
+            "INFO",
+            "validate",
+            "--input-object",
+            gtfs_test_folder.__str__(),
+            "--report",
+            report_file.__str__(),
+        ],
+    )
+    assert result.exit_code == VALIDATION_ERROR
+
+    with open(report_file, "r") as fp:
+        report: Dict = json.load(fp)
+    assert report["issues"] == 3
+    assert report["errors"] == 2
+    assert report["warnings"] == 1
+    assert {(finding["check"], finding["severity"]) for finding in report["findings"]} == {
+        ("timezones", "warning"),
+        ("stop_sequences", "error"),
+        ("languages", "error"),
+    }
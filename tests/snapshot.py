@@ -0,0 +1,26 @@
+import difflib
+import pathlib
+from typing import List
+
+
+def assert_feed_matches_snapshot(output_dir: pathlib.Path, snapshot_dir: pathlib.Path) -> None:
+    """
+    Compare every file of an extracted feed line by line with the golden file of the same name.
+    Missing, additional and changed files fail with a diff against the golden file.
+    """
+    output_files: List[str] = sorted(file.name for file in output_dir.glob("*.txt"))
+    snapshot_files: List[str] = sorted(file.name for file in snapshot_dir.glob("*.txt"))
+    assert output_files == snapshot_files, f"Output files {output_files} differ from the snapshot {snapshot_files}"
+
+    mismatches: List[str] = []
+    file_name: str
+    for file_name in snapshot_files:
+        with open(snapshot_dir.joinpath(file_name), "r", newline="") as fp:
+            expected: List[str] = fp.readlines()
+        with open(output_dir.joinpath(file_name), "r", newline="") as fp:
+            actual: List[str] = fp.readlines()
+        if actual != expected:
+            mismatches.extend(
+                difflib.unified_diff(expected, actual, fromfile=f"snapshot/{file_name}", tofile=f"output/{file_name}")
+            )
+    assert not mismatches, "Output differs from the snapshot:\n" + "".join(mismatches)